@@ -0,0 +1,553 @@
+//! YEET Common - Helpers shared by the v2 and v3 tools
+//!
+//! Both `yeet` (yeet-core) and `yeet-v3` offer the same batch workflow, carry
+//! PNG text chunks through compile and export and honor `SOURCE_DATE_EPOCH`;
+//! that code lives here so the two binaries behave identically. The v3 section
+//! walker is shared too, so `yeet info` and `yeet-v3` read files the same way.
+
+use image::{ImageBuffer, Rgba};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+// ============================================================================
+// Batch File Selection
+// ============================================================================
+
+/// Which files a batch run picks up
+pub struct BatchFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Output tree, skipped while walking when it lives inside the input
+    pub output_dir: Option<PathBuf>,
+}
+
+/// Match a glob against a `/`-separated relative path
+///
+/// `*` and `?` stay within one path component, `**` spans any number of
+/// directories (including none when written as `**/`).
+pub fn glob_match(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            let zero_dirs = match rest {
+                ['/', after @ ..] => glob_match(after, path),
+                _ => false,
+            };
+            zero_dirs || (0..=path.len()).any(|i| glob_match(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| glob_match(rest, &path[i..])),
+        ['?', rest @ ..] => {
+            matches!(path.first(), Some(c) if *c != '/') && glob_match(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && glob_match(rest, &path[1..]),
+    }
+}
+
+/// Whether any pattern matches `relative`; patterns without a `/` match the
+/// file name alone (so `*.png` works at any depth)
+pub fn matches_any(patterns: &[String], relative: &Path) -> bool {
+    let full: Vec<char> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+        .chars()
+        .collect();
+    let name: Vec<char> = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().chars().collect())
+        .unwrap_or_default();
+
+    patterns.iter().any(|pattern| {
+        let glob: Vec<char> = pattern.chars().collect();
+        glob_match(&glob, if pattern.contains('/') { &full } else { &name })
+    })
+}
+
+/// Recursively collect the files under `dir` selected by the include and
+/// exclude globs (excluded directories are not entered)
+pub fn collect_batch_files(
+    root: &Path,
+    dir: &Path,
+    filter: &BatchFilter,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), std::io::Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if matches_any(&filter.exclude, relative) {
+            continue;
+        }
+
+        if path.is_dir() {
+            // Never descend into the output tree when it lives inside the input
+            if filter.output_dir.as_deref().is_some_and(|out| path.starts_with(out)) {
+                continue;
+            }
+            collect_batch_files(root, &path, filter, files)?;
+        } else if matches_any(&filter.include, relative) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Batch Progress and Reports
+// ============================================================================
+
+/// Single-line progress bar on stderr, safe to advance from worker threads
+pub struct BatchProgress {
+    total: usize,
+    done: AtomicUsize,
+    output: Mutex<()>,
+}
+
+impl BatchProgress {
+    const WIDTH: usize = 30;
+
+    pub fn new(total: usize) -> Self {
+        Self { total, done: AtomicUsize::new(0), output: Mutex::new(()) }
+    }
+
+    /// Count one finished file and redraw the bar
+    pub fn advance(&self, name: &str) {
+        let _guard = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        let done = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+        let filled = Self::WIDTH * done / self.total.max(1);
+        eprint!(
+            "\r[{}{}] {}/{} {:<40.40}",
+            "#".repeat(filled),
+            "-".repeat(Self::WIDTH - filled),
+            done,
+            self.total,
+            name
+        );
+        if done == self.total {
+            eprintln!();
+        }
+    }
+}
+
+/// One row of a batch report
+pub trait ReportEntry: Serialize {
+    /// CSV header line, without the trailing newline
+    const CSV_HEADER: &'static str;
+
+    /// Field values in `CSV_HEADER` order, not yet quoted
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+/// Quote a CSV field when it contains separators, quotes or line breaks
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write the per-file batch report; `.csv` paths get CSV, anything else JSON
+pub fn write_batch_report<T: ReportEntry>(path: &Path, entries: &[T]) -> Result<(), std::io::Error> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    let report = if is_csv {
+        let mut report = format!("{}\n", T::CSV_HEADER);
+        for entry in entries {
+            let fields: Vec<String> = entry.csv_fields().iter().map(|f| csv_field(f)).collect();
+            report.push_str(&fields.join(","));
+            report.push('\n');
+        }
+        report
+    } else {
+        serde_json::to_string_pretty(entries)? + "\n"
+    };
+
+    fs::write(path, report)
+}
+
+// ============================================================================
+// PNG Text Chunks
+// ============================================================================
+
+/// Latin-1 text longer than this is written as zTXt instead of tEXt
+const ZTXT_THRESHOLD: usize = 1024;
+
+/// iTXt keyword of an XMP packet in PNG
+pub const XMP_PNG_KEYWORD: &str = "XML:com.adobe.xmp";
+
+pub const METERS_PER_INCH: f64 = 0.0254;
+
+/// Keyword/text pairs from every tEXt, zTXt and iTXt chunk of a PNG
+///
+/// Chunks after the image data are included; a repeated keyword keeps its last value.
+pub fn source_text_chunks(path: &Path) -> BTreeMap<String, String> {
+    let mut text = BTreeMap::new();
+    let Ok(file) = File::open(path) else {
+        return text;
+    };
+    let Ok(mut reader) = png::Decoder::new(std::io::BufReader::new(file)).read_info() else {
+        return text;
+    };
+    // Skips the pixels but still parses the chunks that follow them
+    if let Err(e) = reader.finish() {
+        eprintln!("[WARN] Could not read text chunks after the image data: {}", e);
+    }
+
+    let info = reader.info();
+    for chunk in &info.uncompressed_latin1_text {
+        text.insert(chunk.keyword.clone(), chunk.text.clone());
+    }
+    for chunk in &info.compressed_latin1_text {
+        match chunk.get_text() {
+            Ok(value) => { text.insert(chunk.keyword.clone(), value); }
+            Err(e) => eprintln!("[WARN] Skipping zTXt '{}': {}", chunk.keyword, e),
+        }
+    }
+    for chunk in &info.utf8_text {
+        match chunk.get_text() {
+            Ok(value) => { text.insert(chunk.keyword.clone(), value); }
+            Err(e) => eprintln!("[WARN] Skipping iTXt '{}': {}", chunk.keyword, e),
+        }
+    }
+    text
+}
+
+/// PNG keywords are 1-79 Latin-1 characters
+pub fn valid_text_keyword(keyword: &str) -> bool {
+    (1..=79).contains(&keyword.chars().count()) && is_latin1(keyword)
+}
+
+fn is_latin1(text: &str) -> bool {
+    text.chars().all(|c| (c as u32) <= 0xFF)
+}
+
+/// Queue `text` on the encoder: tEXt for short Latin-1 values, zTXt for long ones, iTXt otherwise
+///
+/// XMP is always an uncompressed iTXt chunk, as the XMP specification asks.
+pub fn add_text_chunks<W: Write>(
+    encoder: &mut png::Encoder<W>,
+    text: &BTreeMap<String, String>,
+) -> Result<(), png::EncodingError> {
+    for (keyword, value) in text {
+        if !valid_text_keyword(keyword) {
+            eprintln!("[WARN] Skipping text '{}': PNG keywords are 1-79 Latin-1 characters", keyword);
+            continue;
+        }
+        if !is_latin1(value) || keyword == XMP_PNG_KEYWORD {
+            encoder.add_itxt_chunk(keyword.clone(), value.clone())?;
+        } else if value.len() > ZTXT_THRESHOLD {
+            encoder.add_ztxt_chunk(keyword.clone(), value.clone())?;
+        } else {
+            encoder.add_text_chunk(keyword.clone(), value.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Save an RGBA image as PNG, with `dpi` as a `pHYs` chunk and `text` as text chunks
+pub fn save_png(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    path: &Path,
+    dpi: Option<(u32, u32)>,
+    text: &BTreeMap<String, String>,
+) -> Result<(), std::io::Error> {
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(File::create(path)?), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let ppm = |dpi: u32| (dpi as f64 / METERS_PER_INCH).round() as u32;
+    encoder.set_pixel_dims(dpi.map(|(x, y)| png::PixelDimensions {
+        xppu: ppm(x),
+        yppu: ppm(y),
+        unit: png::Unit::Meter,
+    }));
+    add_text_chunks(&mut encoder, text).map_err(std::io::Error::other)?;
+    let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+    writer.write_image_data(image.as_raw()).map_err(std::io::Error::other)?;
+    writer.finish().map_err(std::io::Error::other)
+}
+
+// ============================================================================
+// Reproducible Builds
+// ============================================================================
+
+/// `SOURCE_DATE_EPOCH` (seconds since 1970, UTC), which replaces the current time in `created`
+pub fn source_date_epoch() -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+    let Ok(value) = env::var("SOURCE_DATE_EPOCH") else {
+        return Ok(None);
+    };
+    value.trim().parse::<i64>().ok()
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+        .map(Some)
+        .ok_or_else(|| format!("Invalid SOURCE_DATE_EPOCH '{}' (expected seconds since 1970)", value))
+}
+
+// ============================================================================
+// YEET v3 Layout
+// ============================================================================
+//
+// Section order (docs/SPEC_v3.md, "Byte Layout"): header and metadata,
+// embedded zstd dictionary, thumbnail, ICC profile, frames, pyramid levels.
+// These functions only find the sections; decoding them is up to the caller.
+
+/// Flag bit of an embedded thumbnail
+pub const V3_THUMBNAIL_FLAG: u8 = 0b10000000;
+
+/// Fixed v3 header and the raw metadata JSON
+pub struct V3Header {
+    pub flags: u8,
+    pub width: u32,
+    pub height: u32,
+    pub frame_count: u32,
+    pub loop_count: u32,
+    pub metadata: Vec<u8>,
+}
+
+/// Thumbnail as stored: dimensions and data compressed with the file's codec
+pub struct V3Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Sections between the metadata and the ICC profile
+pub struct V3Sections {
+    pub dictionary: Option<Vec<u8>>,
+    pub thumbnail: Option<V3Thumbnail>,
+}
+
+/// Position of one frame or pyramid level payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V3Chunk {
+    pub offset: u64,
+    pub len: u32,
+    /// Frame delay in milliseconds (pyramid levels have none)
+    pub delay: Option<u32>,
+}
+
+/// Frame and pyramid tables, read without loading the payloads
+pub struct V3FrameTable {
+    pub frames: Vec<V3Chunk>,
+    /// Level 1 of every frame, then level 2, ...
+    pub levels: Vec<Vec<V3Chunk>>,
+    /// Offset just past the last section
+    pub end: u64,
+}
+
+pub fn read_u32(reader: &mut impl Read) -> Result<u32, std::io::Error> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Read exactly `len` bytes without trusting `len` for the allocation up front
+pub fn read_bytes(reader: &mut impl Read, len: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut data = Vec::new();
+    reader.take(len as u64).read_to_end(&mut data)?;
+    if data.len() != len {
+        return Err(truncated());
+    }
+    Ok(data)
+}
+
+fn truncated() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Truncated YEET file")
+}
+
+/// Read the magic, version, header fields and metadata of a v3 file
+pub fn read_v3_header(reader: &mut impl Read) -> Result<V3Header, std::io::Error> {
+    let mut preamble = [0u8; 6];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[0..4] != b"YEET" {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Invalid YEET file: wrong magic bytes"
+        ));
+    }
+    if preamble[4] != 3 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Expected v3, got v{}", preamble[4])
+        ));
+    }
+    
+    let width = read_u32(reader)?;
+    let height = read_u32(reader)?;
+    let frame_count = read_u32(reader)?;
+    let loop_count = read_u32(reader)?;
+    let metadata_len = read_u32(reader)? as usize;
+    let metadata = read_bytes(reader, metadata_len)?;
+    Ok(V3Header { flags: preamble[5], width, height, frame_count, loop_count, metadata })
+}
+
+/// Read the embedded dictionary and thumbnail that follow the metadata
+///
+/// Whether a dictionary is embedded is recorded in the metadata
+/// (`zstd_dictionary.embedded`), which the caller has parsed.
+pub fn read_v3_sections(
+    reader: &mut impl Read,
+    header: &V3Header,
+    embedded_dictionary: bool,
+) -> Result<V3Sections, std::io::Error> {
+    let dictionary = if embedded_dictionary {
+        let len = read_u32(reader)? as usize;
+        Some(read_bytes(reader, len)?)
+    } else {
+        None
+    };
+    let thumbnail = if header.flags & V3_THUMBNAIL_FLAG != 0 {
+        let width = read_u32(reader)?;
+        let height = read_u32(reader)?;
+        let len = read_u32(reader)? as usize;
+        Some(V3Thumbnail { width, height, data: read_bytes(reader, len)? })
+    } else {
+        None
+    };
+    Ok(V3Sections { dictionary, thumbnail })
+}
+
+/// Read the ICC profile section (empty when the file has none)
+pub fn read_v3_icc_profile(reader: &mut impl Read) -> Result<Vec<u8>, std::io::Error> {
+    let len = read_u32(reader)? as usize;
+    read_bytes(reader, len)
+}
+
+/// Walk the frames and `pyramid_levels` levels after the ICC profile
+///
+/// Payloads are skipped, not read; a payload running past the end of the
+/// stream is an error.
+pub fn read_v3_frame_table(
+    reader: &mut (impl Read + Seek),
+    frame_count: u32,
+    pyramid_levels: u32,
+) -> Result<V3FrameTable, std::io::Error> {
+    let start = reader.stream_position()?;
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+    
+    fn read_chunk<R: Read + Seek>(reader: &mut R, stream_len: u64, with_delay: bool) -> Result<V3Chunk, std::io::Error> {
+        let delay = if with_delay { Some(read_u32(reader)?) } else { None };
+        let len = read_u32(reader)?;
+        let offset = reader.stream_position()?;
+        if offset + len as u64 > stream_len {
+            return Err(truncated());
+        }
+        reader.seek(SeekFrom::Start(offset + len as u64))?;
+        Ok(V3Chunk { offset, len, delay })
+    }
+    let frames = (0..frame_count)
+        .map(|_| read_chunk(reader, stream_len, true))
+        .collect::<Result<Vec<_>, _>>()?;
+    let levels = (0..pyramid_levels)
+        .map(|_| (0..frame_count).map(|_| read_chunk(reader, stream_len, false)).collect())
+        .collect::<Result<Vec<Vec<_>>, _>>()?;
+    Ok(V3FrameTable { frames, levels, end: reader.stream_position()? })
+}
+
+/// Description of an ICC profile (`desc` tag, v2 text or v4 multi-localized)
+pub fn icc_description(profile: &[u8]) -> Option<String> {
+    let be32 = |data: &[u8], offset: usize| -> Option<usize> {
+        Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+    let tag_count = be32(profile, 128)?;
+    let entry = (0..tag_count)
+        .map(|index| 132 + index * 12)
+        .take_while(|&entry| entry < profile.len())
+        .find(|&entry| profile.get(entry..entry + 4) == Some(b"desc"))?;
+    let (offset, size) = (be32(profile, entry + 4)?, be32(profile, entry + 8)?);
+    let tag = profile.get(offset..offset.checked_add(size)?)?;
+    
+    match tag.get(0..4)? {
+        // textDescriptionType: ASCII length, then the NUL-terminated text
+        b"desc" => {
+            let len = be32(tag, 8)?;
+            let text = tag.get(12..len.checked_add(12)?)?;
+            Some(String::from_utf8_lossy(text).trim_end_matches('\0').to_string())
+        }
+        // multiLocalizedUnicodeType: first record's UTF-16BE string
+        b"mluc" => {
+            let (len, start) = (be32(tag, 20)?, be32(tag, 24)?);
+            let units: Vec<u16> = tag.get(start..start.checked_add(len)?)?
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, path: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let path: Vec<char> = path.chars().collect();
+        glob_match(&pattern, &path)
+    }
+
+    #[test]
+    fn glob_star_stays_in_one_component() {
+        assert!(glob("*.png", "photo.png"));
+        assert!(glob("*.png", ".png"));
+        assert!(!glob("*.png", "photo.jpg"));
+        assert!(!glob("*.png", "raw/photo.png"));
+        assert!(glob("raw/*.png", "raw/photo.png"));
+        assert!(!glob("raw/*.png", "raw/2024/photo.png"));
+    }
+
+    #[test]
+    fn glob_question_mark_matches_one_character() {
+        assert!(glob("img?.png", "img1.png"));
+        assert!(!glob("img?.png", "img.png"));
+        assert!(!glob("img?.png", "img12.png"));
+        assert!(!glob("a?b", "a/b"));
+    }
+
+    #[test]
+    fn glob_double_star_spans_directories() {
+        assert!(glob("**/*.png", "photo.png"));
+        assert!(glob("**/*.png", "a/b/c/photo.png"));
+        assert!(glob("raw/**/*.png", "raw/photo.png"));
+        assert!(glob("raw/**/*.png", "raw/2024/01/photo.png"));
+        assert!(!glob("raw/**/*.png", "edited/photo.png"));
+        assert!(glob("thumbs/**", "thumbs/a/b.png"));
+    }
+
+    #[test]
+    fn glob_literal_and_empty_patterns() {
+        assert!(glob("a.png", "a.png"));
+        assert!(!glob("a.png", "b.png"));
+        assert!(glob("", ""));
+        assert!(!glob("", "a"));
+    }
+
+    #[test]
+    fn patterns_without_slash_match_the_file_name() {
+        let patterns = vec!["*.png".to_string()];
+        assert!(matches_any(&patterns, Path::new("deep/dir/photo.png")));
+        let patterns = vec!["raw/*.png".to_string()];
+        assert!(matches_any(&patterns, Path::new("raw/photo.png")));
+        assert!(!matches_any(&patterns, Path::new("deep/raw/photo.png")));
+    }
+}
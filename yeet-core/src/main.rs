@@ -17,7 +17,7 @@
 //!
 //! Batch convert:
 //! ```bash
-//! yeet batch ./photos --compress --binary
+//! yeet batch ./photos --compress --binary --output ./converted --report report.json
 //! ```
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
@@ -25,12 +25,21 @@
 use eframe::egui;
 use egui_extras::RetainedImage;
 use image::{self, GenericImageView, ImageBuffer, Rgba};
+use rayon::prelude::*;
 use std::{
+    collections::BTreeMap,
     env,
     fs::{self, File},
-    io::{Read, Write},
-    path::PathBuf,
-    time::SystemTime,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use yeet_common::{
+    collect_batch_files, icc_description, read_v3_frame_table, read_v3_header, read_v3_icc_profile,
+    read_v3_sections, save_png, source_date_epoch, source_text_chunks, valid_text_keyword,
+    write_batch_report, BatchFilter, BatchProgress, ReportEntry,
 };
 use flate2::Compression;
 use flate2::write::ZlibEncoder;
@@ -70,15 +79,24 @@ static TEMP_RESULT_PATH: &str = "temp.png";
 // ============================================================================
 
 /// Metadata embedded in YEET v2 files
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 struct YeetMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     created: Option<String>,
     software: String,
     // Future v3 fields (forward compatibility)
+    #[serde(skip_serializing_if = "Option::is_none")]
     color_profile: Option<String>,  // ICC profile name
+    #[serde(skip_serializing_if = "Option::is_none")]
     frame_count: Option<u32>,        // Animation frames
+    #[serde(skip_serializing_if = "Option::is_none")]
     frame_delay: Option<u32>,        // Delay in milliseconds
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    text: BTreeMap<String, String>,  // PNG text chunks by keyword
+    #[serde(flatten)]
+    extra: serde_json::Map<String, Value>,  // Unknown keys, written back unchanged
 }
 
 impl YeetMetadata {
@@ -86,58 +104,138 @@ impl YeetMetadata {
     fn new() -> Self {
         Self {
             author: None,
-            created: Some(format!("{:?}", SystemTime::now())),
+            created: Some(source_date_epoch().ok().flatten().unwrap_or_else(chrono::Utc::now).to_rfc3339()),
             software: "YEET v2.0".to_string(),
             color_profile: None,
             frame_count: None,
             frame_delay: None,
+            text: BTreeMap::new(),
+            extra: serde_json::Map::new(),
         }
     }
 
-    /// Serialize metadata to JSON string
+    /// Serialize metadata to JSON string (known keys first, in a fixed order)
     fn to_json(&self) -> String {
-        let mut json = String::from("{");
+        serde_json::to_string(self).expect("metadata always serializes")
+    }
+
+    /// Parse metadata from its JSON object
+    ///
+    /// Unknown keys are kept as they are in `extra`.
+    fn from_json(json: &str) -> Result<Self, String> {
+        let object = match serde_json::from_str::<Value>(json).map_err(|e| e.to_string())? {
+            Value::Object(object) => object,
+            _ => return Err("metadata is not a JSON object".to_string()),
+        };
         
-        if let Some(ref author) = self.author {
-            json.push_str(&format!("\"author\":\"{}\",", author));
+        let mut metadata = Self {
+            author: None,
+            created: None,
+            software: String::new(),
+            color_profile: None,
+            frame_count: None,
+            frame_delay: None,
+            text: BTreeMap::new(),
+            extra: serde_json::Map::new(),
+        };
+        for (key, value) in object {
+            match (key.as_str(), value) {
+                (_, Value::Null) if Self::KEYS.contains(&key.as_str()) => metadata.remove(&key)?,
+                ("text", Value::Object(text)) => {
+                    for (keyword, value) in text {
+                        match value {
+                            Value::String(value) => metadata.set(&format!("text.{}", keyword), &value)?,
+                            _ => return Err(format!("Text chunk '{}' is not a string", keyword)),
+                        }
+                    }
+                }
+                ("text", _) => return Err("'text' is not an object".to_string()),
+                ("frame_count" | "frame_delay", Value::Number(number)) => {
+                    metadata.set(&key, &number.to_string())?;
+                }
+                (_, Value::String(value)) if Self::KEYS.contains(&key.as_str()) => metadata.set(&key, &value)?,
+                (_, _) if Self::KEYS.contains(&key.as_str()) => {
+                    return Err(format!("Invalid value for '{}'", key));
+                }
+                (_, value) => {
+                    metadata.extra.insert(key, value);
+                }
+            }
         }
-        
-        if let Some(ref created) = self.created {
-            json.push_str(&format!("\"created\":\"{}\",", created));
+        Ok(metadata)
+    }
+    
+    /// Keys `set` and `remove` accept
+    const KEYS: &'static [&'static str] = &[
+        "author", "created", "software", "color_profile", "frame_count", "frame_delay",
+    ];
+    
+    /// Set a field from its text form (numbers for the frame fields)
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if let Some(keyword) = key.strip_prefix("text.") {
+            if !valid_text_keyword(keyword) {
+                return Err(format!("Invalid text keyword '{}' (PNG keywords are 1-79 Latin-1 characters)", keyword));
+            }
+            self.text.insert(keyword.to_string(), value.to_string());
+            return Ok(());
         }
-        
-        json.push_str(&format!("\"software\":\"{}\"", self.software));
-        
-        // v3 forward-compatible fields
-        if let Some(ref profile) = self.color_profile {
-            json.push_str(&format!(",\"color_profile\":\"{}\"", profile));
+        let number = || value.parse::<u32>().map_err(|_| format!(
+            "Invalid value for '{}': expected a number, got '{}'",
+            key, value
+        ));
+        match key {
+            "author" => self.author = Some(value.to_string()),
+            "created" => self.created = Some(value.to_string()),
+            "software" => self.software = value.to_string(),
+            "color_profile" => self.color_profile = Some(value.to_string()),
+            "frame_count" => self.frame_count = Some(number()?),
+            "frame_delay" => self.frame_delay = Some(number()?),
+            _ => return Err(Self::unknown_key(key)),
         }
-        if let Some(frames) = self.frame_count {
-            json.push_str(&format!(",\"frame_count\":{}", frames));
+        Ok(())
+    }
+    
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        if let Some(keyword) = key.strip_prefix("text.") {
+            self.text.remove(keyword);
+            return Ok(());
         }
-        if let Some(delay) = self.frame_delay {
-            json.push_str(&format!(",\"frame_delay\":{}", delay));
+        if self.extra.remove(key).is_some() {
+            return Ok(());
         }
-        
-        json.push_str("}");
-        json
+        match key {
+            "author" => self.author = None,
+            "created" => self.created = None,
+            "software" => return Err("'software' is required and cannot be removed".to_string()),
+            "color_profile" => self.color_profile = None,
+            "frame_count" => self.frame_count = None,
+            "frame_delay" => self.frame_delay = None,
+            _ => return Err(Self::unknown_key(key)),
+        }
+        Ok(())
     }
-
-    /// Parse metadata from JSON string (simple parser)
-    #[allow(dead_code)]
-    fn from_json(json: &str) -> Self {
-        let mut metadata = Self::new();
-        
-        // Parse author
-        if let Some(author_start) = json.find("\"author\":\"") {
-            if let Some(author_end) = json[author_start + 10..].find("\"") {
-                metadata.author = Some(
-                    json[author_start + 10..author_start + 10 + author_end].to_string()
-                );
-            }
+    
+    fn unknown_key(key: &str) -> String {
+        format!("Unknown metadata key '{}' (expected {}, text.<keyword>)", key, Self::KEYS.join(", "))
+    }
+    
+    /// Fields that are set, in file order
+    fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        if let Some(ref author) = self.author { fields.push(("author".to_string(), author.clone())); }
+        if let Some(ref created) = self.created { fields.push(("created".to_string(), created.clone())); }
+        fields.push(("software".to_string(), self.software.clone()));
+        if let Some(ref profile) = self.color_profile { fields.push(("color_profile".to_string(), profile.clone())); }
+        if let Some(frames) = self.frame_count { fields.push(("frame_count".to_string(), frames.to_string())); }
+        if let Some(delay) = self.frame_delay { fields.push(("frame_delay".to_string(), delay.to_string())); }
+        for (keyword, value) in &self.text {
+            fields.push((format!("text.{}", keyword), value.clone()));
         }
-        
-        metadata
+        for (key, value) in &self.extra {
+            let value = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+            fields.push((key.clone(), value));
+        }
+        fields
     }
 }
 
@@ -145,9 +243,12 @@ impl YeetMetadata {
 // Compression Functions
 // ============================================================================
 
-/// Compress data using zlib
-fn compress_data(data: &[u8]) -> Vec<u8> {
-    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+/// Default zlib level (`--level` overrides it)
+const DEFAULT_ZLIB_LEVEL: u32 = 9;
+
+/// Compress data using zlib at `level` (0-9)
+fn compress_data(data: &[u8], level: u32) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
     encoder.write_all(data).unwrap();
     encoder.finish().unwrap()
 }
@@ -164,20 +265,66 @@ fn decompress_data(data: &[u8]) -> Vec<u8> {
 // PNG to YEET Conversion (v2)
 // ============================================================================
 
+/// Size figures of a finished PNG to YEET v2 conversion
+struct ConversionStats {
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    text_chunks: usize,
+    data_len: usize,
+    file_len: usize,
+}
+
 /// Convert PNG image to YEET v2 format
 ///
 /// # Arguments
 ///
 /// * `path` - Path to input PNG file
-/// * `compress` - Enable zlib compression
+/// * `compression` - zlib level, or `None` for no compression
 /// * `use_binary` - Use binary encoding instead of hex text
+/// * `reproducible` - Leave out `created` unless `SOURCE_DATE_EPOCH` fixes it
 ///
 /// # Returns
 ///
 /// Result indicating success or IO error
-fn png_to_yeet_v2(path: PathBuf, compress: bool, use_binary: bool) -> Result<(), std::io::Error> {
+fn png_to_yeet_v2(path: PathBuf, compression: Option<u32>, use_binary: bool, reproducible: bool) -> Result<(), std::io::Error> {
+    let Some(path_str) = path.to_str() else {
+        return Ok(());
+    };
+    let path_to_yeet = path_str.replace(".png", ".yeet");
+    
+    let stats = write_yeet_v2(&path, Path::new(&path_to_yeet), compression, use_binary, reproducible)?;
+    
+    // Print conversion statistics
+    let original_size = stats.width * stats.height * if stats.has_alpha { 4 } else { 3 };
+    let compression_ratio = 100.0 * (1.0 - (stats.data_len as f64 / original_size as f64));
+    
+    println!("[OK] Converted to YEET v2: {}", path_to_yeet);
+    println!("  Dimensions: {}x{}", stats.width, stats.height);
+    println!("  Alpha: {}", stats.has_alpha);
+    println!("  Binary mode: {}", use_binary);
+    match compression {
+        Some(level) => println!("  Compressed: true, level {} ({:.1}% reduction)", level, compression_ratio),
+        None => println!("  Compressed: false ({:.1}% reduction)", compression_ratio),
+    }
+    if stats.text_chunks > 0 {
+        println!("  Text chunks: {}", stats.text_chunks);
+    }
+    println!("  Final size: {} bytes", stats.file_len);
+    
+    Ok(())
+}
+
+/// Convert the PNG at `input` and write the YEET v2 file to `output`
+fn write_yeet_v2(
+    input: &Path,
+    output: &Path,
+    compression: Option<u32>,
+    use_binary: bool,
+    reproducible: bool,
+) -> Result<ConversionStats, std::io::Error> {
     // Load and analyze image
-    let img = image::open(&path).expect("File not found!");
+    let img = image::open(input).map_err(std::io::Error::other)?;
     let width = img.width();
     let height = img.height();
     
@@ -188,9 +335,34 @@ fn png_to_yeet_v2(path: PathBuf, compress: bool, use_binary: bool) -> Result<(),
     );
     
     // Prepare metadata
-    let metadata = YeetMetadata::new();
-    let metadata_json = metadata.to_json();
+    let mut metadata = YeetMetadata::new();
+    metadata.text = source_text_chunks(input);
+    if reproducible && !matches!(source_date_epoch(), Ok(Some(_))) {
+        metadata.created = None;
+    }
+    
+    // Text chunks can push the metadata past the u16 length field; the
+    // largest ones are dropped until it fits
+    let mut metadata_json = metadata.to_json();
+    while metadata_json.len() > u16::MAX as usize {
+        let Some((keyword, size)) = metadata.text.iter()
+            .map(|(keyword, value)| (keyword.clone(), keyword.len() + value.len()))
+            .max_by_key(|&(_, size)| size)
+        else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Metadata is {} bytes, but v2 allows at most {}", metadata_json.len(), u16::MAX)
+            ));
+        };
+        eprintln!(
+            "[WARN] {}: dropping text chunk '{}' ({} bytes), v2 metadata is limited to {} bytes",
+            input.display(), keyword, size, u16::MAX
+        );
+        metadata.text.remove(&keyword);
+        metadata_json = metadata.to_json();
+    }
     let metadata_bytes = metadata_json.as_bytes();
+    let metadata_len = metadata_bytes.len() as u16;
     
     // Encode pixel data
     let pixel_data = if use_binary {
@@ -222,10 +394,9 @@ fn png_to_yeet_v2(path: PathBuf, compress: bool, use_binary: bool) -> Result<(),
     };
     
     // Apply compression if requested
-    let (final_data, is_compressed) = if compress {
-        (compress_data(&pixel_data), true)
-    } else {
-        (pixel_data, false)
+    let (final_data, is_compressed) = match compression {
+        Some(level) => (compress_data(&pixel_data, level), true),
+        None => (pixel_data, false),
     };
     
     // Build flags byte
@@ -235,36 +406,29 @@ fn png_to_yeet_v2(path: PathBuf, compress: bool, use_binary: bool) -> Result<(),
     if use_binary { flags |= 0b00000100; }
     
     // Write YEET file
-    if let Some(path_str) = path.to_str() {
-        let path_to_yeet = path_str.replace(".png", ".yeet");
-        let mut file = File::create(&path_to_yeet)?;
-        
-        // Write header
-        file.write_all(b"YEET")?;                                     // Magic bytes
-        file.write_all(&[2])?;                                        // Version
-        file.write_all(&[flags])?;                                    // Flags
-        file.write_all(&width.to_le_bytes())?;                        // Width
-        file.write_all(&height.to_le_bytes())?;                       // Height
-        file.write_all(&(metadata_bytes.len() as u16).to_le_bytes())?; // Metadata length
-        file.write_all(metadata_bytes)?;                              // Metadata JSON
-        file.write_all(&(final_data.len() as u32).to_le_bytes())?;   // Data length
-        file.write_all(&final_data)?;                                 // Pixel data
-        
-        file.flush()?;
-        
-        // Print conversion statistics
-        let original_size = width * height * if has_alpha { 4 } else { 3 };
-        let compression_ratio = 100.0 * (1.0 - (final_data.len() as f64 / original_size as f64));
-        
-        println!("[OK] Converted to YEET v2: {}", path_to_yeet);
-        println!("  Dimensions: {}x{}", width, height);
-        println!("  Alpha: {}", has_alpha);
-        println!("  Binary mode: {}", use_binary);
-        println!("  Compressed: {} ({:.1}% reduction)", is_compressed, compression_ratio);
-        println!("  Final size: {} bytes", final_data.len() + 20 + metadata_bytes.len());
-    }
+    let mut file = File::create(output)?;
     
-    Ok(())
+    // Write header
+    file.write_all(b"YEET")?;                                     // Magic bytes
+    file.write_all(&[2])?;                                        // Version
+    file.write_all(&[flags])?;                                    // Flags
+    file.write_all(&width.to_le_bytes())?;                        // Width
+    file.write_all(&height.to_le_bytes())?;                       // Height
+    file.write_all(&metadata_len.to_le_bytes())?;                 // Metadata length
+    file.write_all(metadata_bytes)?;                              // Metadata JSON
+    file.write_all(&(final_data.len() as u32).to_le_bytes())?;   // Data length
+    file.write_all(&final_data)?;                                 // Pixel data
+    
+    file.flush()?;
+    
+    Ok(ConversionStats {
+        width,
+        height,
+        has_alpha,
+        text_chunks: metadata.text.len(),
+        data_len: final_data.len(),
+        file_len: final_data.len() + 20 + metadata_bytes.len(),
+    })
 }
 
 // ============================================================================
@@ -281,6 +445,18 @@ fn png_to_yeet_v2(path: PathBuf, compress: bool, use_binary: bool) -> Result<(),
 ///
 /// Tuple of (width, height) of the image
 fn yeet_to_png_v2(path: PathBuf) -> (u32, u32) {
+    let (img_buffer, _) = read_yeet_v2(path);
+    
+    // Save temporary PNG for viewing
+    img_buffer.save(TEMP_RESULT_PATH).expect("Failed to save temp PNG");
+    
+    img_buffer.dimensions()
+}
+
+/// Decode a YEET v2 (or legacy v1) file into an RGBA image and its metadata
+///
+/// v1 files have no metadata; malformed v2 metadata is reported and skipped.
+fn read_yeet_v2(path: PathBuf) -> (ImageBuffer<Rgba<u8>, Vec<u8>>, Option<YeetMetadata>) {
     let mut file = File::open(&path).expect("Couldn't open file");
     let mut contents = Vec::new();
     file.read_to_end(&mut contents).expect("Couldn't read file");
@@ -290,7 +466,7 @@ fn yeet_to_png_v2(path: PathBuf) -> (u32, u32) {
         // Fallback to v1 format
         eprintln!("[INFO] Detected v1 format, using legacy parser");
         drop(file);
-        return yeet_to_png_v1(path);
+        return (yeet_to_image_v1(path), None);
     }
     
     eprintln!("[INFO] Detected v2 format");
@@ -309,7 +485,7 @@ fn yeet_to_png_v2(path: PathBuf) -> (u32, u32) {
     offset += 1;
     
     if version == 1 {
-        return yeet_to_png_v1(path);
+        return (yeet_to_image_v1(path), None);
     } else if version != 2 {
         panic!("Unsupported YEET version: {}", version);
     }
@@ -343,7 +519,10 @@ fn yeet_to_png_v2(path: PathBuf) -> (u32, u32) {
     let metadata_len = u16::from_le_bytes([contents[offset], contents[offset + 1]]) as usize;
     offset += 2;
     
-    let _metadata_json = String::from_utf8_lossy(&contents[offset..offset + metadata_len]);
+    let metadata_json = String::from_utf8_lossy(&contents[offset..offset + metadata_len]);
+    let metadata = YeetMetadata::from_json(&metadata_json)
+        .map_err(|e| eprintln!("[WARN] Ignoring malformed metadata: {}", e))
+        .ok();
     offset += metadata_len;
     
     // Read data length
@@ -393,21 +572,18 @@ fn yeet_to_png_v2(path: PathBuf) -> (u32, u32) {
         })
     };
     
-    // Save temporary PNG for viewing
-    img_buffer.save(TEMP_RESULT_PATH).expect("Failed to save temp PNG");
-    
-    (width, height)
+    (img_buffer, metadata)
 }
 
 // ============================================================================
 // YEET v1 Legacy Support
 // ============================================================================
 
-/// Convert legacy YEET v1 file to PNG
+/// Decode a legacy YEET v1 file
 ///
 /// This function provides backward compatibility with the original YEET format.
 /// v1 format: 8-byte header (width + height) + hex color codes (RRGGBB)
-fn yeet_to_png_v1(path: PathBuf) -> (u32, u32) {
+fn yeet_to_image_v1(path: PathBuf) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
     let mut contents: Vec<u8> = fs::read(&path).expect("Couldn't read file.");
     let binding: Vec<_> = contents.drain(0..8).collect();
     
@@ -434,9 +610,477 @@ fn yeet_to_png_v1(path: PathBuf) -> (u32, u32) {
         Rgba([0, 0, 0, 255])
     });
     
-    img_buffer.save(TEMP_RESULT_PATH).expect("Failed to save temp PNG");
+    img_buffer
+}
+
+// ============================================================================
+// Batch Conversion
+// ============================================================================
+
+/// Options for the 'batch' command
+struct BatchOptions {
+    compression: Option<u32>,
+    binary: bool,
+    reproducible: bool,
+    filter: BatchFilter,
+}
+
+/// Outcome of converting one file in a batch
+#[derive(Serialize)]
+struct BatchEntry {
+    input: PathBuf,
+    output: PathBuf,
+    input_size: u64,
+    output_size: u64,
+    ratio: f64,
+    time_seconds: f64,
+    error: Option<String>,
+}
+
+impl ReportEntry for BatchEntry {
+    const CSV_HEADER: &'static str = "input,output,input_size,output_size,ratio,time_seconds,error";
+    
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.input.display().to_string(),
+            self.output.display().to_string(),
+            self.input_size.to_string(),
+            self.output_size.to_string(),
+            format!("{:.4}", self.ratio),
+            format!("{:.4}", self.time_seconds),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Convert one batch file, mirroring its relative path under the output directory
+fn convert_batch_file(root: &Path, input: &Path, options: &BatchOptions) -> BatchEntry {
+    let relative = input.strip_prefix(root).unwrap_or(input);
+    let output = match &options.filter.output_dir {
+        Some(dir) => dir.join(relative).with_extension("yeet"),
+        None => input.with_extension("yeet"),
+    };
+    
+    let start = Instant::now();
+    let result = (|| {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_yeet_v2(input, &output, options.compression, options.binary, options.reproducible)
+    })();
+    
+    let (output_size, error) = match result {
+        Ok(stats) => (stats.file_len as u64, None),
+        Err(e) => (0, Some(e.to_string())),
+    };
+    
+    let input_size = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+    BatchEntry {
+        input: input.to_path_buf(),
+        output,
+        input_size,
+        output_size,
+        ratio: if input_size == 0 { 0.0 } else { output_size as f64 / input_size as f64 },
+        time_seconds: start.elapsed().as_secs_f64(),
+        error,
+    }
+}
+
+// ============================================================================
+// Metadata Editing (v2)
+// ============================================================================
+
+/// Offset of the metadata length (magic, version, flags, width, height)
+const METADATA_OFFSET: usize = 14;
+
+/// A v2 file split around its metadata; everything else stays raw bytes
+struct MetadataSections {
+    header: Vec<u8>,
+    metadata: YeetMetadata,
+    payload: Vec<u8>,
+}
+
+impl MetadataSections {
+    fn read(path: &Path) -> Result<Self, std::io::Error> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < METADATA_OFFSET + 2 || &bytes[0..4] != b"YEET" {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Not a YEET v2 file (v1 files have no metadata)"
+            ));
+        }
+        if bytes[4] != 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Expected v2, got v{} (use yeet-v3 meta for v3 files)", bytes[4])
+            ));
+        }
+        
+        let metadata_len = u16::from_le_bytes([bytes[METADATA_OFFSET], bytes[METADATA_OFFSET + 1]]) as usize;
+        let payload_start = METADATA_OFFSET + 2 + metadata_len;
+        let metadata_bytes = bytes.get(METADATA_OFFSET + 2..payload_start).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Truncated YEET file")
+        })?;
+        let metadata = YeetMetadata::from_json(&String::from_utf8_lossy(metadata_bytes))
+            .map_err(|e| std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Malformed metadata: {}", e)
+            ))?;
+        
+        Ok(Self {
+            header: bytes[..METADATA_OFFSET].to_vec(),
+            metadata,
+            payload: bytes[payload_start..].to_vec(),
+        })
+    }
     
-    (width, height)
+    /// Write the header and payload unchanged around the new metadata
+    ///
+    /// The v2 metadata length is a u16, so larger metadata is refused.
+    fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let metadata_json = self.metadata.to_json();
+        let metadata_len = u16::try_from(metadata_json.len()).map_err(|_| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Metadata is {} bytes, but v2 allows at most {}", metadata_json.len(), u16::MAX)
+        ))?;
+        
+        let mut bytes = Vec::with_capacity(self.header.len() + 2 + metadata_json.len() + self.payload.len());
+        bytes.extend_from_slice(&self.header);
+        bytes.extend_from_slice(&metadata_len.to_le_bytes());
+        bytes.extend_from_slice(metadata_json.as_bytes());
+        bytes.extend_from_slice(&self.payload);
+        fs::write(path, bytes)
+    }
+}
+
+// ============================================================================
+// File Inspection
+// ============================================================================
+
+/// One entry of the frame table
+struct FrameInfo {
+    offset: usize,
+    delay: Option<u32>,
+    size: usize,
+}
+
+/// Structure of a YEET file of any version, read without decoding the pixels
+struct FileInfo {
+    file_size: usize,
+    version: u8,
+    flags: Option<u8>,
+    compression: String,
+    /// Named flag bits and whether they are set
+    flag_bits: Vec<(&'static str, bool)>,
+    width: u32,
+    height: u32,
+    loop_count: Option<u32>,
+    metadata: Option<String>,
+    icc: Option<(usize, Option<String>)>,
+    thumbnail: Option<(u32, u32, usize)>,
+    dictionary_size: Option<usize>,
+    pyramid: Option<(u32, usize)>,
+    frames: Vec<FrameInfo>,
+    /// Bytes after the last known section
+    trailing_size: usize,
+    /// Pixel bytes before compression (hex text counts two bytes per sample)
+    raw_size: u64,
+}
+
+impl FileInfo {
+    fn stored_size(&self) -> usize {
+        self.frames.iter().map(|frame| frame.size).sum()
+    }
+    
+    /// Stored frame bytes relative to `raw_size`
+    fn ratio(&self) -> f64 {
+        if self.raw_size == 0 {
+            return 0.0;
+        }
+        self.stored_size() as f64 / self.raw_size as f64
+    }
+    
+    fn print(&self, path: &Path) {
+        println!("[INFO] {} ({} bytes)", path.display(), self.file_size);
+        println!("  Version: {}", self.version);
+        if let Some(flags) = self.flags {
+            let set: Vec<&str> = self.flag_bits.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+            let set = if set.is_empty() { "none".to_string() } else { set.join(", ") };
+            println!("  Flags: 0b{:08b} ({})", flags, set);
+        }
+        println!("  Compression: {}", self.compression);
+        println!("  Dimensions: {}x{}", self.width, self.height);
+        match self.loop_count {
+            Some(loops) => println!("  Frames: {} (loop count {})", self.frames.len(), loops),
+            None => println!("  Frames: {}", self.frames.len()),
+        }
+        if let Some(ref metadata) = self.metadata {
+            println!("  Metadata: {} bytes", metadata.len());
+            println!("    {}", metadata);
+        }
+        if let Some((size, ref description)) = self.icc {
+            match description {
+                Some(description) => println!("  ICC profile: {} bytes ({})", size, description),
+                None => println!("  ICC profile: {} bytes", size),
+            }
+        }
+        if let Some((width, height, size)) = self.thumbnail {
+            println!("  Thumbnail: {}x{}, {} bytes", width, height, size);
+        }
+        if let Some(size) = self.dictionary_size {
+            println!("  Zstd dictionary: embedded, {} bytes", size);
+        }
+        if let Some((levels, size)) = self.pyramid {
+            println!("  Pyramid: {} levels, {} bytes", levels, size);
+        }
+        if self.trailing_size > 0 {
+            println!("  Trailing data: {} bytes", self.trailing_size);
+        }
+        println!("  Frame table:");
+        println!("    {:>5}  {:>10}  {:>8}  {:>10}", "#", "Offset", "Delay", "Size");
+        for (index, frame) in self.frames.iter().enumerate() {
+            let delay = frame.delay.map_or("-".to_string(), |delay| delay.to_string());
+            println!("    {:>5}  {:>10}  {:>8}  {:>10}", index + 1, frame.offset, delay, frame.size);
+        }
+        println!(
+            "  Pixel data: {} bytes stored, {} bytes raw ({:.1}%)",
+            self.stored_size(), self.raw_size, self.ratio() * 100.0
+        );
+    }
+    
+    fn to_json(&self, path: &Path) -> String {
+        let flags = self.flags.map(|flags| {
+            let mut object = serde_json::Map::new();
+            object.insert("value".to_string(), json!(flags));
+            for (name, on) in &self.flag_bits {
+                object.insert(name.to_string(), json!(on));
+            }
+            Value::Object(object)
+        });
+        // Metadata is embedded as JSON when it parses, otherwise as a string
+        let metadata = self.metadata.as_ref().map(|metadata| {
+            serde_json::from_str::<Value>(metadata).unwrap_or_else(|_| json!(metadata))
+        });
+        let icc = self.icc.as_ref().map(|(size, description)| json!({
+            "size": size,
+            "description": description,
+        }));
+        let thumbnail = self.thumbnail.map(|(width, height, size)| json!({
+            "width": width,
+            "height": height,
+            "size": size,
+        }));
+        let pyramid = self.pyramid.map(|(levels, size)| json!({ "levels": levels, "size": size }));
+        let frames: Vec<Value> = self.frames.iter().map(|frame| json!({
+            "offset": frame.offset,
+            "delay": frame.delay,
+            "size": frame.size,
+        })).collect();
+        
+        json!({
+            "file": path.display().to_string(),
+            "file_size": self.file_size,
+            "version": self.version,
+            "flags": flags,
+            "compression": self.compression,
+            "width": self.width,
+            "height": self.height,
+            "loop_count": self.loop_count,
+            "metadata": metadata,
+            "icc_profile": icc,
+            "thumbnail": thumbnail,
+            "zstd_dictionary_size": self.dictionary_size,
+            "pyramid": pyramid,
+            "frames": frames,
+            "trailing_size": self.trailing_size,
+            "stored_size": self.stored_size(),
+            "raw_size": self.raw_size,
+            "ratio": (self.ratio() * 10_000.0).round() / 10_000.0,
+        }).to_string()
+    }
+}
+
+/// Cursor over the file bytes that reports truncation as an error
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], std::io::Error> {
+        let end = self.offset.checked_add(len).filter(|&end| end <= self.bytes.len()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Truncated file: {} bytes needed at offset {}", len, self.offset)
+            )
+        })?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+    
+    fn u16(&mut self) -> Result<u16, std::io::Error> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    
+    fn u32(&mut self) -> Result<u32, std::io::Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Read the structure of a v1, v2 or v3 file
+fn inspect_file(path: &Path) -> Result<FileInfo, std::io::Error> {
+    let bytes = fs::read(path)?;
+    if !bytes.starts_with(b"YEET") {
+        return inspect_v1(&bytes);
+    }
+    
+    let mut reader = ByteReader { bytes: &bytes, offset: 4 };
+    let version = reader.take(1)?[0];
+    let flags = reader.take(1)?[0];
+    match version {
+        2 => inspect_v2(reader, flags),
+        3 => inspect_v3(&bytes),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported YEET version: {}", version)
+        )),
+    }
+}
+
+/// v1: width and height (native-endian u32) followed by RRGGBB hex text
+fn inspect_v1(bytes: &[u8]) -> Result<FileInfo, std::io::Error> {
+    let mut reader = ByteReader { bytes, offset: 0 };
+    let width = u32::from_ne_bytes(reader.take(4)?.try_into().unwrap());
+    let height = u32::from_ne_bytes(reader.take(4)?.try_into().unwrap());
+    Ok(FileInfo {
+        file_size: bytes.len(),
+        version: 1,
+        flags: None,
+        compression: "none".to_string(),
+        flag_bits: Vec::new(),
+        width,
+        height,
+        loop_count: None,
+        metadata: None,
+        icc: None,
+        thumbnail: None,
+        dictionary_size: None,
+        pyramid: None,
+        frames: vec![FrameInfo { offset: 8, delay: None, size: bytes.len() - 8 }],
+        trailing_size: 0,
+        raw_size: width as u64 * height as u64 * 6,
+    })
+}
+
+fn inspect_v2(mut reader: ByteReader, flags: u8) -> Result<FileInfo, std::io::Error> {
+    let is_compressed = (flags & 0b00000001) != 0;
+    let has_alpha = (flags & 0b00000010) != 0;
+    let is_binary = (flags & 0b00000100) != 0;
+    
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let metadata_len = reader.u16()? as usize;
+    let metadata = String::from_utf8_lossy(reader.take(metadata_len)?).into_owned();
+    let data_len = reader.u32()? as usize;
+    let offset = reader.offset;
+    reader.take(data_len)?;
+    
+    let bytes_per_pixel = if has_alpha { 4 } else { 3 } * if is_binary { 1 } else { 2 };
+    Ok(FileInfo {
+        file_size: reader.bytes.len(),
+        version: 2,
+        flags: Some(flags),
+        compression: if is_compressed { "zlib" } else { "none" }.to_string(),
+        flag_bits: vec![("compressed", is_compressed), ("alpha", has_alpha), ("binary", is_binary)],
+        width,
+        height,
+        loop_count: None,
+        metadata: Some(metadata),
+        icc: None,
+        thumbnail: None,
+        dictionary_size: None,
+        pyramid: None,
+        frames: vec![FrameInfo { offset, delay: None, size: data_len }],
+        trailing_size: reader.bytes.len() - reader.offset,
+        raw_size: width as u64 * height as u64 * bytes_per_pixel,
+    })
+}
+
+/// v3 layout as written by yeet-v3, walked with the reader yeet-v3 uses
+fn inspect_v3(bytes: &[u8]) -> Result<FileInfo, std::io::Error> {
+    let mut reader = Cursor::new(bytes);
+    let header = read_v3_header(&mut reader)?;
+    let flags = header.flags;
+    let has_alpha = (flags & 0b00000100) != 0;
+    let is_binary = (flags & 0b00001000) != 0;
+    let has_icc = (flags & 0b00100000) != 0;
+    let is_hdr = (flags & 0b01000000) != 0;
+    
+    let metadata = String::from_utf8_lossy(&header.metadata).into_owned();
+    let fields: serde_json::Map<String, Value> = serde_json::from_str(&metadata).map_err(|e| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Malformed metadata: {}", e)
+    ))?;
+    let field = |key: &str| fields.get(key).and_then(Value::as_str);
+    
+    // Codecs added after v3.0 are named in the metadata instead of the flag bits
+    let compression = match (field("codec"), flags & 0b00000011) {
+        (Some(codec), _) => codec,
+        (None, 1) => "zlib",
+        (None, 2) => "brotli",
+        (None, 3) => "zstd",
+        (None, _) => "none",
+    };
+    let embedded_dictionary = fields.get("zstd_dictionary")
+        .and_then(|dictionary| dictionary.get("embedded"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let levels = fields.get("pyramid_levels")
+        .and_then(Value::as_u64)
+        .and_then(|levels| u32::try_from(levels).ok())
+        .unwrap_or(0);
+    
+    let sections = read_v3_sections(&mut reader, &header, embedded_dictionary)?;
+    let icc_profile = read_v3_icc_profile(&mut reader)?;
+    let table = read_v3_frame_table(&mut reader, header.frame_count, levels)?;
+    
+    let icc = (!icc_profile.is_empty()).then(|| (icc_profile.len(), icc_description(&icc_profile)));
+    let frames = table.frames.iter()
+        .map(|chunk| FrameInfo { offset: chunk.offset as usize, delay: chunk.delay, size: chunk.len as usize })
+        .collect();
+    let pyramid_size = table.levels.iter().flatten().map(|chunk| chunk.len as usize).sum();
+    
+    let channels = match field("color_model") {
+        Some("cmyk") => 4,
+        _ => 3,
+    } + u64::from(has_alpha);
+    let bytes_per_pixel = channels * if is_hdr { 2 } else { 1 } * if is_binary { 1 } else { 2 };
+    Ok(FileInfo {
+        file_size: bytes.len(),
+        version: 3,
+        flags: Some(flags),
+        compression: compression.to_string(),
+        flag_bits: vec![
+            ("alpha", has_alpha),
+            ("binary", is_binary),
+            ("animated", (flags & 0b00010000) != 0),
+            ("icc", has_icc),
+            ("hdr", is_hdr),
+            ("thumbnail", sections.thumbnail.is_some()),
+        ],
+        width: header.width,
+        height: header.height,
+        loop_count: Some(header.loop_count),
+        metadata: Some(metadata),
+        icc,
+        thumbnail: sections.thumbnail.map(|thumbnail| (thumbnail.width, thumbnail.height, thumbnail.data.len())),
+        dictionary_size: sections.dictionary.map(|dictionary| dictionary.len()),
+        pyramid: (levels > 0).then_some((levels, pyramid_size)),
+        frames,
+        trailing_size: bytes.len() - table.end as usize,
+        raw_size: header.width as u64 * header.height as u64 * bytes_per_pixel * header.frame_count as u64,
+    })
 }
 
 // ============================================================================
@@ -458,6 +1102,9 @@ fn main() -> Result<(), eframe::Error> {
     match command.as_str() {
         "compile" => handle_compile(&args),
         "batch" => handle_batch(&args),
+        "meta" => handle_meta(&args),
+        "export" => handle_export(&args),
+        "info" | "inspect" => handle_info(&args),
         "help" | "--help" | "-h" => {
             print_usage(&args[0]);
             Ok(())
@@ -474,15 +1121,16 @@ fn main() -> Result<(), eframe::Error> {
 fn handle_compile(args: &[String]) -> Result<(), eframe::Error> {
     if args.len() < 3 {
         eprintln!("[ERROR] No input file specified");
-        eprintln!("Usage: {} compile <file.png> [--compress] [--binary]", args[0]);
+        eprintln!("Usage: {} compile <file.png> [--compress] [--binary] [--reproducible]", args[0]);
         std::process::exit(1);
     }
     
     let path: PathBuf = (&args[2]).into();
-    let compress = args.contains(&"--compress".to_string());
+    let compression = compression_from_args(args);
     let binary = args.contains(&"--binary".to_string());
+    let reproducible = reproducible_from_args(args);
     
-    match png_to_yeet_v2(path, compress, binary) {
+    match png_to_yeet_v2(path, compression, binary, reproducible) {
         Ok(()) => {
             println!("[OK] Successfully converted PNG to YEET v2");
             Ok(())
@@ -498,48 +1146,213 @@ fn handle_compile(args: &[String]) -> Result<(), eframe::Error> {
 fn handle_batch(args: &[String]) -> Result<(), eframe::Error> {
     if args.len() < 3 {
         eprintln!("[ERROR] No directory specified");
-        eprintln!("Usage: {} batch <directory> [--compress] [--binary]", args[0]);
+        eprintln!("Usage: {} batch <directory> [--compress] [--binary] [--output <dir>]", args[0]);
         std::process::exit(1);
     }
     
     let dir_path = PathBuf::from(&args[2]);
-    let compress = args.contains(&"--compress".to_string());
-    let binary = args.contains(&"--binary".to_string());
     
     if !dir_path.is_dir() {
         eprintln!("[ERROR] Not a valid directory: {:?}", dir_path);
         std::process::exit(1);
     }
     
-    let entries = fs::read_dir(&dir_path).expect("Failed to read directory");
-    let mut count = 0;
-    let mut errors = 0;
+    let mut include: Vec<String> = arg_values(args, "--include").map(str::to_string).collect();
+    if include.is_empty() {
+        include.push("*.png".to_string());
+    }
+    let options = BatchOptions {
+        compression: compression_from_args(args),
+        binary: args.contains(&"--binary".to_string()),
+        reproducible: reproducible_from_args(args),
+        filter: BatchFilter {
+            include,
+            exclude: arg_values(args, "--exclude").map(str::to_string).collect(),
+            output_dir: arg_value(args, "--output").map(PathBuf::from),
+        },
+    };
     
-    println!("[INFO] Starting batch conversion...\n");
+    if let Some(threads) = arg_value(args, "--threads") {
+        let pool = threads.parse::<usize>().ok().filter(|&n| n > 0).map(|n| {
+            rayon::ThreadPoolBuilder::new().num_threads(n).build_global()
+        });
+        if !matches!(pool, Some(Ok(()))) {
+            eprintln!("[ERROR] Invalid thread count '{}'", threads);
+            std::process::exit(1);
+        }
+    }
     
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "png" {
-                    println!("Converting: {:?}", path.file_name().unwrap());
-                    match png_to_yeet_v2(path, compress, binary) {
-                        Ok(()) => count += 1,
-                        Err(e) => {
-                            eprintln!("  [ERROR] {}", e);
-                            errors += 1;
-                        }
-                    }
-                    println!();
-                }
-            }
+    let mut files = Vec::new();
+    if let Err(e) = collect_batch_files(&dir_path, &dir_path, &options.filter, &mut files) {
+        eprintln!("[ERROR] Failed to read directory: {}", e);
+        std::process::exit(1);
+    }
+    
+    println!("[INFO] Starting batch conversion of {} files...\n", files.len());
+    
+    let progress = BatchProgress::new(files.len());
+    let entries: Vec<BatchEntry> = files
+        .par_iter()
+        .map(|path| {
+            let entry = convert_batch_file(&dir_path, path, &options);
+            progress.advance(&path.strip_prefix(&dir_path).unwrap_or(path).display().to_string());
+            entry
+        })
+        .collect();
+    
+    for entry in &entries {
+        if let Some(ref error) = entry.error {
+            eprintln!("  [ERROR] {}: {}", entry.input.display(), error);
         }
     }
     
+    let errors = entries.iter().filter(|e| e.error.is_some()).count();
+    let input_total: u64 = entries.iter().filter(|e| e.error.is_none()).map(|e| e.input_size).sum();
+    let output_total: u64 = entries.iter().map(|e| e.output_size).sum();
+    
+    println!();
     println!("[OK] Batch conversion complete");
-    println!("  Successful: {}", count);
+    println!("  Successful: {}", entries.len() - errors);
     println!("  Failed: {}", errors);
+    println!("  Total size: {} -> {} bytes", input_total, output_total);
+    
+    if let Some(report) = arg_value(args, "--report") {
+        match write_batch_report(Path::new(report), &entries) {
+            Ok(()) => println!("  Report: {}", report),
+            Err(e) => {
+                eprintln!("[ERROR] Failed to write report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Handle 'meta' command: show or edit metadata without touching the pixel data
+fn handle_meta(args: &[String]) -> Result<(), eframe::Error> {
+    let (Some(action), Some(input)) = (args.get(2), args.get(3)) else {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} meta <show|set|rm|strip> <file.yeet> [key=value ...|key ...] [--output out.yeet]", args[0]);
+        std::process::exit(1);
+    };
+    let input = PathBuf::from(input);
+    
+    let mut sections = match MetadataSections::read(&input) {
+        Ok(sections) => sections,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load {}: {}", input.display(), e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Everything after the file name except `--output <path>`
+    let mut operands = Vec::new();
+    let mut rest = args[4..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--output" {
+            rest.next();
+        } else {
+            operands.push(arg.as_str());
+        }
+    }
+    
+    let metadata = &mut sections.metadata;
+    let result = match action.as_str() {
+        "show" => {
+            println!("[INFO] Metadata of {}:", input.display());
+            for (key, value) in metadata.fields() {
+                println!("  {}: {}", key, value);
+            }
+            return Ok(());
+        }
+        "set" if operands.is_empty() => Err("Nothing to set (expected key=value)".to_string()),
+        "set" => operands.iter().try_for_each(|assignment| match assignment.split_once('=') {
+            Some((key, value)) => metadata.set(key, value),
+            None => Err(format!("Expected key=value, got '{}'", assignment)),
+        }),
+        "rm" if operands.is_empty() => Err("Nothing to remove (expected a key)".to_string()),
+        "rm" => operands.iter().try_for_each(|key| metadata.remove(key)),
+        // Everything but the (required) software field
+        "strip" => {
+            metadata.text.clear();
+            metadata.extra.clear();
+            YeetMetadata::KEYS.iter()
+                .filter(|&&key| key != "software")
+                .try_for_each(|key| metadata.remove(key))
+        }
+        _ => Err(format!("Unknown meta action '{}' (expected show, set, rm or strip)", action)),
+    };
+    if let Err(e) = result {
+        eprintln!("[ERROR] {}", e);
+        std::process::exit(1);
+    }
+    
+    let output = arg_value(args, "--output").map(PathBuf::from).unwrap_or(input);
+    match sections.write(&output) {
+        Ok(()) => {
+            println!("[OK] Wrote {}", output.display());
+            for (key, value) in sections.metadata.fields() {
+                println!("  {}: {}", key, value);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle 'export' command: decode to PNG, carrying the text metadata along
+fn handle_export(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file specified");
+        eprintln!("Usage: {} export <file.yeet> [--output out.png]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    if !path.exists() {
+        eprintln!("[ERROR] File not found: {:?}", path);
+        std::process::exit(1);
+    }
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.with_extension("png"));
+    
+    let (image, metadata) = read_yeet_v2(path);
+    let text = metadata.map(|m| m.text).unwrap_or_default();
+    if let Err(e) = save_png(&image, &output, None, &text) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
     
+    println!("[OK] Exported: {}", output.display());
+    println!("  Dimensions: {}x{}", image.width(), image.height());
+    if !text.is_empty() {
+        println!("  Text chunks: {}", text.len());
+    }
+    Ok(())
+}
+
+/// Handle 'info' command: print the structure of a v1, v2 or v3 file
+fn handle_info(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file specified");
+        eprintln!("Usage: {} info <file.yeet> [--json]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    match inspect_file(&path) {
+        Ok(info) if args.contains(&"--json".to_string()) => println!("{}", info.to_json(&path)),
+        Ok(info) => info.print(&path),
+        Err(e) => {
+            eprintln!("[ERROR] Failed to inspect {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
     Ok(())
 }
 
@@ -570,6 +1383,48 @@ fn handle_view(file_path: &str) -> Result<(), eframe::Error> {
     )
 }
 
+/// zlib level selected by `--compress` / `--level`, exiting on invalid input
+fn compression_from_args(args: &[String]) -> Option<u32> {
+    let level = arg_value(args, "--level");
+    if !args.contains(&"--compress".to_string()) {
+        if level.is_some() {
+            eprintln!("[ERROR] --level needs --compress");
+            std::process::exit(1);
+        }
+        return None;
+    }
+    
+    match level.map(str::parse::<u32>) {
+        None => Some(DEFAULT_ZLIB_LEVEL),
+        Some(Ok(level @ 0..=9)) => Some(level),
+        Some(_) => {
+            eprintln!("[ERROR] Invalid zlib level '{}' (expected 0 to 9)", level.unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `--reproducible`, after checking `SOURCE_DATE_EPOCH` (exiting if it is invalid)
+fn reproducible_from_args(args: &[String]) -> bool {
+    if let Err(e) = source_date_epoch() {
+        eprintln!("[ERROR] {}", e);
+        std::process::exit(1);
+    }
+    args.contains(&"--reproducible".to_string())
+}
+
+/// Value following a `--flag value` pair on the command line
+fn arg_value<'a>(args: &'a [String], flag: &'a str) -> Option<&'a str> {
+    arg_values(args, flag).next()
+}
+
+/// Values of every `--flag value` pair for a repeatable flag
+fn arg_values<'a>(args: &'a [String], flag: &'a str) -> impl Iterator<Item = &'a str> {
+    args.windows(2)
+        .filter(move |pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+}
+
 /// Print usage information
 fn print_usage(program: &str) {
     println!("YEET Image Format - Viewer and Converter v2.0");
@@ -577,17 +1432,40 @@ fn print_usage(program: &str) {
     println!("USAGE:");
     println!("  View:    {} <file.yeet>", program);
     println!("  Convert: {} compile <file.png> [--compress] [--binary]", program);
-    println!("  Batch:   {} batch <directory> [--compress] [--binary]", program);
+    println!("  Batch:   {} batch <directory> [--compress] [--binary] [batch options]", program);
+    println!("  Meta:    {} meta <show|set|rm|strip> <file.yeet> [key=value ...|key ...]", program);
+    println!("  Export:  {} export <file.yeet> [--output out.png]", program);
+    println!("  Info:    {} info <file.yeet> [--json]   (v1, v2 and v3)", program);
     println!("  Help:    {} help", program);
     println!();
     println!("OPTIONS:");
     println!("  --compress    Apply zlib compression (40-60% size reduction)");
     println!("  --binary      Use binary encoding instead of hex text");
+    println!("  --level <n>   zlib level 0-9 for --compress (default 9, fastest 1)");
+    println!("  --reproducible  Omit `created` unless SOURCE_DATE_EPOCH is set, so");
+    println!("                  identical input gives byte-identical files");
+    println!("  SOURCE_DATE_EPOCH=<seconds since 1970> is used for `created` instead of now");
+    println!();
+    println!("BATCH OPTIONS:");
+    println!("  --output <dir>       Write into <dir>, mirroring the input tree");
+    println!("  --include <glob>     Files to convert (repeatable, default *.png)");
+    println!("  --exclude <glob>     Files or directories to skip (repeatable)");
+    println!("  --report <file>      Per-file report (.csv for CSV, otherwise JSON)");
+    println!("  --threads <n>        Parallel conversions (default: all cores)");
+    println!();
+    println!("META OPTIONS (pixel data is copied unchanged):");
+    println!("  --output <file>      Write the edited file to <file> instead of in place");
+    println!("  Keys: author, created, software, color_profile, frame_count, frame_delay,");
+    println!("        text.<keyword> (PNG text chunks, e.g. text.Title)");
     println!();
     println!("EXAMPLES:");
     println!("  {} image.yeet", program);
     println!("  {} compile photo.png --compress --binary", program);
     println!("  {} batch ./photos --compress --binary", program);
+    println!("  {} batch ./photos --output ./yeet --exclude 'raw/**' --report report.csv", program);
+    println!("  {} meta set image.yeet author=\"Stijn Jakobs\"", program);
+    println!("  {} export image.yeet --output image.png", program);
+    println!("  {} info image.yeet --json", program);
 }
 
 // ============================================================================
@@ -625,3 +1503,97 @@ impl eframe::App for ImagePreview {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn metadata_json_escapes() {
+        let json = r#"{"author":"A \"quoted\" name\\path\nline\ttab é中","software":"YEET v2.0","text":{"Comment":"slash \/ and \r"}}"#;
+        let metadata = YeetMetadata::from_json(json).unwrap();
+        assert_eq!(metadata.author.as_deref(), Some("A \"quoted\" name\\path\nline\ttab é中"));
+        assert_eq!(metadata.text["Comment"], "slash / and \r");
+        
+        // Written back with the escapes JSON needs and parsed to the same values
+        let again = YeetMetadata::from_json(&metadata.to_json()).unwrap();
+        assert_eq!(again.author, metadata.author);
+        assert_eq!(again.text, metadata.text);
+    }
+    
+    #[test]
+    fn metadata_json_surrogate_pairs() {
+        let metadata = YeetMetadata::from_json(r#"{"author":"smile \ud83d\ude00!","software":"x"}"#).unwrap();
+        assert_eq!(metadata.author.as_deref(), Some("smile 😀!"));
+        
+        // Unpaired surrogates are not valid text
+        assert!(YeetMetadata::from_json(r#"{"author":"\ud83d","software":"x"}"#).is_err());
+        assert!(YeetMetadata::from_json(r#"{"author":"\ude00 alone","software":"x"}"#).is_err());
+    }
+    
+    #[test]
+    fn metadata_json_keeps_unknown_keys() {
+        let json = r#"{"software":"YEET v2.0","flag":true,"future":{"nested":[1,2.5,null]}}"#;
+        let metadata = YeetMetadata::from_json(json).unwrap();
+        assert_eq!(metadata.extra["future"], json!({"nested": [1, 2.5, null]}));
+        assert_eq!(metadata.extra["flag"], json!(true));
+        assert_eq!(metadata.to_json(), json);
+    }
+    
+    #[test]
+    fn metadata_json_rejects_invalid_input() {
+        assert!(YeetMetadata::from_json("[]").is_err());
+        assert!(YeetMetadata::from_json(r#"{"software":"x""#).is_err());
+        assert!(YeetMetadata::from_json(r#"{"software":"x","frame_count":"many"}"#).is_err());
+        assert!(YeetMetadata::from_json(r#"{"software":"x","text":"not an object"}"#).is_err());
+    }
+    
+    /// `examples/animated-v3.yeet` is written by yeet-v3's `write_yeet_v3_to`
+    /// (its tests keep the file current)
+    #[test]
+    fn inspect_v3_reads_a_file_written_by_yeet_v3() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/animated-v3.yeet");
+        let bytes = fs::read(&path).unwrap();
+        let info = inspect_file(&path).unwrap();
+        
+        assert_eq!(info.version, 3);
+        assert_eq!(info.file_size, bytes.len());
+        assert_eq!(info.compression, "lz4");
+        let set: Vec<&str> = info.flag_bits.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+        assert_eq!(set, ["alpha", "binary", "animated", "thumbnail"]);
+        assert_eq!((info.width, info.height), (12, 10));
+        assert_eq!(info.loop_count, Some(3));
+        assert!(info.icc.is_none());
+        assert!(info.dictionary_size.is_none());
+        
+        let metadata: Value = serde_json::from_str(info.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(metadata["created"], "2024-01-01T00:00:00+00:00");
+        assert_eq!(metadata["tiles"], json!({"width": 12, "height": 4}));
+        
+        let (thumb_width, thumb_height, thumb_len) = info.thumbnail.unwrap();
+        assert_eq!((thumb_width, thumb_height), (2, 2));
+        assert!(thumb_len > 0);
+        
+        // Frames sit back to back, each behind its delay and length
+        let delays: Vec<Option<u32>> = info.frames.iter().map(|frame| frame.delay).collect();
+        assert_eq!(delays, [Some(100), Some(250)]);
+        assert_eq!(info.frames[1].offset, info.frames[0].offset + info.frames[0].size + 8);
+        let frame_end = info.frames[1].offset + info.frames[1].size;
+        
+        // One pyramid level per frame, each with a length prefix, then nothing
+        let (levels, pyramid_size) = info.pyramid.unwrap();
+        assert_eq!(levels, 1);
+        assert_eq!(frame_end + 2 * 4 + pyramid_size, bytes.len());
+        assert_eq!(info.trailing_size, 0);
+        assert_eq!(info.raw_size, 12 * 10 * 4 * 2);
+    }
+    
+    #[test]
+    fn inspect_v3_rejects_truncated_files() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/animated-v3.yeet");
+        let bytes = fs::read(path).unwrap();
+        for len in [6, 22, 30, bytes.len() - 1] {
+            assert!(inspect_v3(&bytes[..len]).is_err(), "{} bytes", len);
+        }
+    }
+}
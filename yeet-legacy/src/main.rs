@@ -11,11 +11,11 @@
 
 use eframe::egui;
 use egui_extras::RetainedImage;
-use image::{ImageBuffer, Rgba};
+use image::{GenericImageView, ImageBuffer, Rgba};
 use std::{
     env,
     fs::{self, File},
-    io::{Read, Write},
+    io::Write,
     path::PathBuf,
 };
 
@@ -17,12 +17,22 @@
 use eframe::egui;
 use egui_extras::RetainedImage;
 use image::{ImageBuffer, Rgba};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
     env,
     fs::{self, File},
-    io::{Read, Write},
-    path::PathBuf,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    collections::BTreeMap,
+    sync::Arc,
+    time::Instant,
+};
+use yeet_common::{
+    collect_batch_files, icc_description, read_bytes, read_u32, read_v3_frame_table, read_v3_header,
+    read_v3_icc_profile, read_v3_sections, save_png, source_date_epoch, source_text_chunks,
+    valid_text_keyword, write_batch_report, BatchFilter, BatchProgress, ReportEntry, V3Chunk,
+    METERS_PER_INCH, XMP_PNG_KEYWORD,
 };
 
 // ============================================================================
@@ -33,28 +43,52 @@ use std::{
 // - Magic bytes: "YEET" (4 bytes)
 // - Version: 3 (1 byte)
 // - Flags: (1 byte)
-//   - Bit 0-1: Compression (00=none, 01=zlib, 10=brotli, 11=zstd)
+//   - Bit 0-1: Compression (00=none, 01=zlib, 10=brotli, 11=zstd); overridden
+//     by the metadata `codec` key, which names codecs without bits (lz4, lzma)
 //   - Bit 2: Alpha channel (0=RGB, 1=RGBA)
 //   - Bit 3: Binary mode (0=hex, 1=binary)
 //   - Bit 4: Animation (0=single, 1=multi-frame)
 //   - Bit 5: ICC profile embedded (0=no, 1=yes)
 //   - Bit 6: HDR mode (0=8-bit, 1=16-bit)
-//   - Bit 7: Reserved
+//   - Bit 7: Thumbnail embedded (0=no, 1=yes)
 // - Width: u32 (4 bytes)
 // - Height: u32 (4 bytes)
 // - Frame count: u32 (4 bytes, 1 for static images)
 // - Loop count: u32 (4 bytes, 0=infinite)
 // - Metadata length: u32 (4 bytes)
 // - Metadata: JSON (variable)
+// - Zstd dictionary (only if metadata `zstd_dictionary.embedded`): length u32,
+//   dictionary bytes; otherwise `zstd_dictionary.id` names an external one
+// - Thumbnail (only if bit 7 is set): width u32, height u32, data length u32,
+//   data (8-bit sRGB RGBA, compressed like the frames)
 // - ICC profile length: u32 (4 bytes, 0 if none)
 // - ICC profile data: (variable)
 // - Frame data: (variable, repeated for animations)
 //
+// Pixel data: RGB(A) per pixel, 1 byte per sample or 2 bytes big-endian in
+// HDR mode; hex mode stores the same bytes as uppercase hex text.
+// The metadata `color_model` key switches the samples to CMYK(A) or CIELAB(A)
+// (lcms2 Lab v4 encoding, D50). CMYK files must embed their ICC profile.
+//
 // Frame structure (for animations):
 // - Frame delay: u32 (4 bytes, milliseconds)
 // - Frame data length: u32 (4 bytes)
 // - Frame pixel data: (variable)
 //
+// Tiled frames (metadata `tiles` set) replace the pixel data with:
+// - Tile count: u32 (4 bytes, columns × rows in row-major order)
+// - Tile data lengths: u32 per tile (offset table)
+// - Tile data: each tile compressed on its own, rows of the tile only
+//   (edge tiles are clipped to the image)
+//
+// Interlaced frames (metadata `interlace` = "adam7", untiled only) store the
+// pixels in PNG Adam7 pass order, so a prefix of the data gives a coarse image.
+// Pyramid levels are never interlaced.
+//
+// Pyramid (metadata `pyramid_levels` > 0), after the last frame:
+// - For each level (1 = half size, 2 = quarter, ... rounded up), for each frame:
+//   data length u32 + data, laid out like the frames (tiled if they are)
+//
 // ============================================================================
 
 static TEMP_RESULT_PATH: &str = "temp_v3.png";
@@ -74,6 +108,8 @@ struct YeetMetadataV3 {
     // Color management
     color_profile: Option<String>,
     color_space: Option<String>,
+    #[serde(default)]
+    color_model: ColorModel,
     
     // Animation
     frame_count: u32,
@@ -83,11 +119,40 @@ struct YeetMetadataV3 {
     // Technical
     bit_depth: u8,
     hdr: bool,
+    #[serde(default)]
+    codec: Option<String>,
+    #[serde(default)]
+    zstd_dictionary: Option<DictionaryRef>,
+    #[serde(default)]
+    tiles: Option<TileLayout>,
+    #[serde(default)]
+    pyramid_levels: u32,
+    #[serde(default)]
+    interlace: Interlace,
+    #[serde(default)]
+    quality: Quality,
     
     // Extended EXIF-like data
     camera: Option<CameraMetadata>,
     dpi: Option<(u32, u32)>,
-    orientation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    orientation: Option<Orientation>,
+    /// Raw EXIF (TIFF structure, hex) kept by `compile --keep-exif`
+    #[serde(default)]
+    exif: Option<String>,
+    /// XMP packet (XML) from the source or `embed-xmp`
+    #[serde(default)]
+    xmp: Option<String>,
+    /// PNG tEXt/zTXt/iTXt chunks by keyword (Title, Description, Copyright, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    text: BTreeMap<String, String>,
+    
+    /// Application data; YEET tools keep it but never interpret it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    custom: BTreeMap<String, serde_json::Value>,
+    /// Keys this version does not know (written by newer tools), kept as-is
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,22 +165,319 @@ struct CameraMetadata {
     focal_length: Option<String>,
 }
 
+impl CameraMetadata {
+    /// "Canon EOS R5, 1/1000 s, f/2.8, ISO 100, 50 mm" from whatever is known
+    fn summary(&self) -> String {
+        let parts = [
+            self.body(),
+            self.exposure.as_ref().map(|e| format!("{} s", e)),
+            self.aperture.clone(),
+            self.iso.map(|iso| format!("ISO {}", iso)),
+            self.focal_length.clone(),
+        ];
+        parts.into_iter().flatten().collect::<Vec<_>>().join(", ")
+    }
+    
+    /// Make and model, without repeating the make when the model includes it
+    fn body(&self) -> Option<String> {
+        match (&self.make, &self.model) {
+            (Some(make), Some(model)) if model.starts_with(make.as_str()) => Some(model.clone()),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.clone().or_else(|| model.clone()),
+        }
+    }
+}
+
+/// Color model of the stored pixels (alpha, if any, is an extra trailing channel)
+///
+/// Older files have no `color_model` key and are RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ColorModel {
+    #[default]
+    Rgb,
+    Cmyk,
+    Lab,
+}
+
+impl ColorModel {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rgb" => Some(ColorModel::Rgb),
+            "cmyk" => Some(ColorModel::Cmyk),
+            "lab" | "cielab" => Some(ColorModel::Lab),
+            _ => None,
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            ColorModel::Rgb => "RGB",
+            ColorModel::Cmyk => "CMYK",
+            ColorModel::Lab => "CIELAB",
+        }
+    }
+    
+    /// Color channels per pixel, not counting alpha
+    fn channels(self) -> usize {
+        match self {
+            ColorModel::Rgb | ColorModel::Lab => 3,
+            ColorModel::Cmyk => 4,
+        }
+    }
+}
+
+/// How faithfully the stored pixels match the source (metadata `quality`)
+///
+/// Older files have no `quality` key and are lossless. Decoders need not care:
+/// near-lossless pixels are stored like any others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+enum Quality {
+    #[default]
+    Lossless,
+    /// Every color sample is within `max_error` of the source (alpha is exact)
+    NearLossless { max_error: u32 },
+}
+
+impl Quality {
+    fn describe(self) -> String {
+        match self {
+            Quality::Lossless => "lossless".to_string(),
+            Quality::NearLossless { max_error } => format!("near-lossless (max error {})", max_error),
+        }
+    }
+}
+
+/// How the stored pixels must be turned for display (the 8 EXIF orientations)
+///
+/// Rotations are clockwise. Viewers and exports apply it; `compile
+/// --apply-orientation` bakes it into the pixels instead. Written by name;
+/// reading also accepts the EXIF tag values 1-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Orientation {
+    #[default]
+    Normal,
+    MirrorHorizontal,
+    #[serde(rename = "rotate-180")]
+    Rotate180,
+    MirrorVertical,
+    /// Mirrored horizontally, then rotated 270°
+    Transpose,
+    #[serde(rename = "rotate-90")]
+    Rotate90,
+    /// Mirrored horizontally, then rotated 90°
+    Transverse,
+    #[serde(rename = "rotate-270")]
+    Rotate270,
+}
+
+impl Orientation {
+    /// In EXIF order (tag values 1-8)
+    const ALL: [Orientation; 8] = [
+        Orientation::Normal,
+        Orientation::MirrorHorizontal,
+        Orientation::Rotate180,
+        Orientation::MirrorVertical,
+        Orientation::Transpose,
+        Orientation::Rotate90,
+        Orientation::Transverse,
+        Orientation::Rotate270,
+    ];
+    
+    fn name(self) -> &'static str {
+        match self {
+            Orientation::Normal => "normal",
+            Orientation::MirrorHorizontal => "mirror-horizontal",
+            Orientation::Rotate180 => "rotate-180",
+            Orientation::MirrorVertical => "mirror-vertical",
+            Orientation::Transpose => "transpose",
+            Orientation::Rotate90 => "rotate-90",
+            Orientation::Transverse => "transverse",
+            Orientation::Rotate270 => "rotate-270",
+        }
+    }
+    
+    fn from_exif(value: u32) -> Option<Self> {
+        Self::ALL.get((value as usize).wrapping_sub(1)).copied()
+    }
+    
+    /// `"rotate-90"`, `6` or `"6"`
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        match value {
+            serde_json::Value::Number(number) => {
+                number.as_u64().and_then(|value| u32::try_from(value).ok()).and_then(Self::from_exif)
+            }
+            serde_json::Value::String(name) => {
+                let name = name.trim().to_ascii_lowercase();
+                Self::ALL.into_iter().find(|orientation| orientation.name() == name)
+                    .or_else(|| name.parse().ok().and_then(Self::from_exif))
+            }
+            _ => None,
+        }
+    }
+    
+    fn swaps_axes(self) -> bool {
+        matches!(self, Orientation::Transpose | Orientation::Rotate90 | Orientation::Transverse | Orientation::Rotate270)
+    }
+    
+    /// Displayed size of a `width`×`height` stored image
+    fn display_size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_axes() { (height, width) } else { (width, height) }
+    }
+    
+    /// Stored pixel shown at display position (`x`, `y`) of a `width`×`height` stored image
+    fn source_pixel(self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (right, bottom) = (width - 1, height - 1);
+        match self {
+            Orientation::Normal => (x, y),
+            Orientation::MirrorHorizontal => (right - x, y),
+            Orientation::Rotate180 => (right - x, bottom - y),
+            Orientation::MirrorVertical => (x, bottom - y),
+            Orientation::Transpose => (y, x),
+            Orientation::Rotate90 => (y, bottom - x),
+            Orientation::Transverse => (right - y, bottom - x),
+            Orientation::Rotate270 => (right - y, x),
+        }
+    }
+    
+    /// Stored rectangle (x, y, width, height) shown by a display rectangle
+    fn source_rect(self, x: u32, y: u32, width: u32, height: u32, stored: (u32, u32)) -> (u32, u32, u32, u32) {
+        if width == 0 || height == 0 {
+            return (0, 0, 0, 0);
+        }
+        let (x0, y0) = self.source_pixel(x, y, stored.0, stored.1);
+        let (x1, y1) = self.source_pixel(x + width - 1, y + height - 1, stored.0, stored.1);
+        (x0.min(x1), y0.min(y1), x0.abs_diff(x1) + 1, y0.abs_diff(y1) + 1)
+    }
+    
+    /// Reorder `bytes_per_pixel`-sized pixels of a `width`×`height` image for display
+    fn apply(self, data: &[u8], width: u32, height: u32, bytes_per_pixel: usize) -> Vec<u8> {
+        if self == Orientation::Normal {
+            return data.to_vec();
+        }
+        let (display_width, display_height) = self.display_size(width, height);
+        let mut oriented = Vec::with_capacity(data.len());
+        for y in 0..display_height {
+            for x in 0..display_width {
+                let (source_x, source_y) = self.source_pixel(x, y, width, height);
+                let index = (source_y as usize * width as usize + source_x as usize) * bytes_per_pixel;
+                oriented.extend_from_slice(&data[index..index + bytes_per_pixel]);
+            }
+        }
+        oriented
+    }
+    
+    /// Orient a rendered RGBA image
+    fn apply_to_image(self, image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        if self == Orientation::Normal {
+            return image;
+        }
+        let (width, height) = image.dimensions();
+        let (display_width, display_height) = self.display_size(width, height);
+        ImageBuffer::from_raw(display_width, display_height, self.apply(image.as_raw(), width, height, 4))
+            .expect("orientation keeps the pixel count")
+    }
+}
+
+impl<'de> Deserialize<'de> for Orientation {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Self::from_value(&value).ok_or_else(|| {
+            serde::de::Error::custom(format!("unknown orientation {} (expected a name or 1-8)", value))
+        })
+    }
+}
+
+impl YeetMetadataV3 {
+    fn orientation(&self) -> Orientation {
+        self.orientation.unwrap_or_default()
+    }
+    
+    /// Parse the metadata of a file
+    ///
+    /// Only broken JSON and invalid format keys (which decoding depends on)
+    /// are errors. Any other invalid value is reported and replaced by its
+    /// default; the original stays in `extra` when the default is not written
+    /// (e.g. an unknown `orientation` reads as normal but is kept).
+    fn from_json_lenient(bytes: &[u8]) -> Result<Self, std::io::Error> {
+        let malformed = |message: String| std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Malformed metadata: {} (inspect it with `meta show`)", message)
+        );
+        let mut object = match serde_json::from_slice(bytes).map_err(|e| malformed(e.to_string()))? {
+            serde_json::Value::Object(object) => object,
+            _ => return Err(malformed("not a JSON object".to_string())),
+        };
+        if let Ok(metadata) = serde_json::from_value(serde_json::Value::Object(object.clone())) {
+            return Ok(metadata);
+        }
+        
+        let mut invalid = serde_json::Map::new();
+        for (key, value) in &object {
+            if let Err(e) = check_metadata_value(key, value) {
+                if FORMAT_METADATA_KEYS.contains(&key.as_str()) {
+                    return Err(malformed(e));
+                }
+                eprintln!("[WARN] Ignoring invalid metadata '{}': {} (using the default)", key, e);
+                invalid.insert(key.clone(), value.clone());
+            }
+        }
+        object.retain(|key, _| !invalid.contains_key(key));
+        
+        let mut metadata: Self = serde_json::from_value(serde_json::Value::Object(object))
+            .map_err(|e| malformed(e.to_string()))?;
+        let written = serde_json::to_value(&metadata).map_err(std::io::Error::other)?;
+        for (key, value) in invalid {
+            if written.get(&key).is_none() {
+                metadata.extra.insert(key, value);
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// Check one metadata value on its own, against defaults for every other key
+fn check_metadata_value(key: &str, value: &serde_json::Value) -> Result<(), String> {
+    let mut probe = match serde_json::to_value(YeetMetadataV3::default()) {
+        Ok(serde_json::Value::Object(probe)) => probe,
+        _ => return Ok(()),
+    };
+    probe.insert(key.to_string(), value.clone());
+    serde_json::from_value::<YeetMetadataV3>(serde_json::Value::Object(probe))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
 impl Default for YeetMetadataV3 {
     fn default() -> Self {
         Self {
             author: None,
-            created: Some(chrono::Utc::now().to_rfc3339()),
+            created: Some(source_date_epoch().ok().flatten().unwrap_or_else(chrono::Utc::now).to_rfc3339()),
             software: "YEET v3.0-alpha".to_string(),
             color_profile: None,
             color_space: Some("sRGB".to_string()),
+            color_model: ColorModel::Rgb,
             frame_count: 1,
             frame_delay: None,
             loop_count: 0,
             bit_depth: 8,
             hdr: false,
+            codec: None,
+            zstd_dictionary: None,
+            tiles: None,
+            pyramid_levels: 0,
+            interlace: Interlace::None,
+            quality: Quality::Lossless,
             camera: None,
             dpi: None,
-            orientation: Some("normal".to_string()),
+            orientation: Some(Orientation::Normal),
+            exif: None,
+            xmp: None,
+            text: BTreeMap::new(),
+            custom: BTreeMap::new(),
+            extra: serde_json::Map::new(),
         }
     }
 }
@@ -134,11 +496,64 @@ struct YeetImageV3 {
     height: u32,
     has_alpha: bool,
     is_hdr: bool,
+    is_binary: bool,
+    compression: CompressionAlgorithm,
+    compression_level: Option<i32>,  // Encoder setting only, not stored in the file
+    dictionary: Option<ZstdDictionary>,  // Used for zstd data only
+    loop_count: u32,
     metadata: YeetMetadataV3,
+    thumbnail: Option<YeetThumbnail>,
     icc_profile: Option<Vec<u8>>,
     frames: Vec<YeetFrame>,
 }
 
+impl YeetImageV3 {
+    /// Bytes per pixel of the raw (binary) pixel data
+    fn bytes_per_pixel(&self) -> usize {
+        icc_pixel_format(self.metadata.color_model, self.has_alpha, self.is_hdr).bytes_per_pixel()
+    }
+    
+    /// Compress with this image's codec, level and dictionary
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        let dictionary = self.dictionary.as_ref().map(|d| d.data.as_slice());
+        compress_data(data, self.compression, self.compression_level, dictionary)
+    }
+}
+
+/// Small display-ready preview (8-bit sRGB RGBA) stored right after the metadata
+#[derive(Debug, Clone)]
+struct YeetThumbnail {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Everything in front of the ICC profile and frame data
+///
+/// Readable on its own with `read_yeet_v3_header`, so file browsers can show
+/// the thumbnail without decompressing the full raster.
+#[derive(Debug, Clone)]
+struct YeetHeaderV3 {
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    is_hdr: bool,
+    is_binary: bool,
+    has_icc: bool,
+    compression: CompressionAlgorithm,
+    frame_count: u32,
+    loop_count: u32,
+    metadata: YeetMetadataV3,
+    dictionary: Option<ZstdDictionary>,
+    thumbnail: Option<YeetThumbnail>,
+}
+
+impl YeetHeaderV3 {
+    fn bytes_per_pixel(&self) -> usize {
+        icc_pixel_format(self.metadata.color_model, self.has_alpha, self.is_hdr).bytes_per_pixel()
+    }
+}
+
 // ============================================================================
 // Compression (TODO: Add Brotli/Zstd)
 // ============================================================================
@@ -149,673 +564,3657 @@ use flate2::read::ZlibDecoder;
 use brotli::enc::BrotliEncoderParams;
 use std::io::Cursor;
 
-fn compress_data(data: &[u8], algorithm: CompressionAlgorithm) -> Vec<u8> {
+/// Compress with `level`, or the algorithm's strongest setting when `None`
+///
+/// `dictionary` is only used by zstd.
+fn compress_data(
+    data: &[u8],
+    algorithm: CompressionAlgorithm,
+    level: Option<i32>,
+    dictionary: Option<&[u8]>,
+) -> Vec<u8> {
+    let level = level.unwrap_or_else(|| algorithm.default_level());
     match algorithm {
         CompressionAlgorithm::None => data.to_vec(),
         CompressionAlgorithm::Zlib => {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level as u32));
             encoder.write_all(data).unwrap();
             encoder.finish().unwrap()
         }
         CompressionAlgorithm::Brotli => {
             let mut output = Vec::new();
-            let params = BrotliEncoderParams::default();
+            let params = BrotliEncoderParams { quality: level, ..Default::default() };
             let mut reader = Cursor::new(data);
             brotli::BrotliCompress(&mut reader, &mut output, &params).unwrap();
-            println!("[INFO] Brotli compression: {} -> {} bytes ({:.1}% reduction)", 
-                     data.len(), output.len(), 
-                     100.0 * (1.0 - output.len() as f64 / data.len() as f64));
             output
         }
-        CompressionAlgorithm::Zstd => {
-            let compressed = zstd::encode_all(data, 19).unwrap(); // Level 19 = best compression
-            println!("[INFO] Zstd compression: {} -> {} bytes ({:.1}% reduction)", 
-                     data.len(), compressed.len(), 
-                     100.0 * (1.0 - compressed.len() as f64 / data.len() as f64));
-            compressed
+        CompressionAlgorithm::Zstd => match dictionary {
+            Some(dictionary) => {
+                let mut encoder = zstd::stream::write::Encoder::with_dictionary(Vec::new(), level, dictionary).unwrap();
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            None => zstd::encode_all(data, level).unwrap(),
+        },
+        CompressionAlgorithm::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        CompressionAlgorithm::Lzma => {
+            let mut encoder = xz2::write::XzEncoder::new(Vec::new(), level as u32);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
         }
     }
 }
 
-fn decompress_data(data: &[u8], algorithm: CompressionAlgorithm) -> Vec<u8> {
+/// Decompress a complete stream (corrupt or truncated data is an error)
+///
+/// `dictionary` is only used by zstd frames that were compressed with it.
+fn decompress_data(
+    data: &[u8],
+    algorithm: CompressionAlgorithm,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<Vec<u8>, std::io::Error> {
+    let mut decompressed = Vec::new();
     match algorithm {
-        CompressionAlgorithm::None => data.to_vec(),
+        CompressionAlgorithm::None => decompressed.extend_from_slice(data),
         CompressionAlgorithm::Zlib => {
-            let mut decoder = ZlibDecoder::new(data);
-            let mut decompressed = Vec::new();
-            decoder.read_to_end(&mut decompressed).unwrap();
-            decompressed
+            ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
         }
         CompressionAlgorithm::Brotli => {
-            let mut output = Vec::new();
-            let mut reader = Cursor::new(data);
-            brotli::BrotliDecompress(&mut reader, &mut output).unwrap();
-            output
+            brotli::BrotliDecompress(&mut Cursor::new(data), &mut decompressed)?;
         }
-        CompressionAlgorithm::Zstd => {
-            zstd::decode_all(data).unwrap()
+        CompressionAlgorithm::Zstd => match frame_dictionary(data, dictionary)? {
+            Some(dictionary) => {
+                zstd::stream::read::Decoder::with_dictionary(data, dictionary)?.read_to_end(&mut decompressed)?;
+            }
+            None => decompressed = zstd::decode_all(data)?,
+        },
+        CompressionAlgorithm::Lz4 => {
+            lz4_flex::frame::FrameDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+        CompressionAlgorithm::Lzma => {
+            xz2::read::XzDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+    }
+    Ok(decompressed)
+}
+
+/// Decompress as much of a (possibly truncated) stream as possible
+///
+/// Running out of data just ends the output; data that cannot be decoded
+/// at all is an error.
+fn decompress_prefix(
+    data: &[u8],
+    algorithm: CompressionAlgorithm,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<Vec<u8>, std::io::Error> {
+    let mut reader: Box<dyn Read> = match algorithm {
+        CompressionAlgorithm::None => return Ok(data.to_vec()),
+        CompressionAlgorithm::Zlib => Box::new(ZlibDecoder::new(data)),
+        CompressionAlgorithm::Brotli => Box::new(brotli::Decompressor::new(data, 4096)),
+        CompressionAlgorithm::Zstd => match frame_dictionary(data, dictionary)? {
+            Some(dictionary) => Box::new(zstd::stream::read::Decoder::with_dictionary(data, dictionary)?),
+            None => Box::new(zstd::stream::read::Decoder::with_buffer(data)?),
+        },
+        CompressionAlgorithm::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(data)),
+        CompressionAlgorithm::Lzma => Box::new(xz2::read::XzDecoder::new(data)),
+    };
+    
+    let mut output = Vec::new();
+    let mut chunk = [0u8; 16384];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => output.extend_from_slice(&chunk[..n]),
+            Err(e) if output.is_empty() && e.kind() != std::io::ErrorKind::UnexpectedEof => return Err(e),
+            Err(_) => break,
         }
     }
+    Ok(output)
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Compression codec of the frame, tile, thumbnail and pyramid data
+///
+/// Adding a codec means adding a variant, a `CODECS` entry and its arms in
+/// `compress_data`, `decompress_data` and `decompress_prefix`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CompressionAlgorithm {
-    None = 0,
-    Zlib = 1,
-    Brotli = 2,
-    Zstd = 3,
+    None,
+    Zlib,
+    Brotli,
+    Zstd,
+    Lz4,
+    Lzma,
 }
 
-impl From<u8> for CompressionAlgorithm {
-    fn from(value: u8) -> Self {
-        match value & 0b00000011 {
-            0 => CompressionAlgorithm::None,
-            1 => CompressionAlgorithm::Zlib,
-            2 => CompressionAlgorithm::Brotli,
-            3 => CompressionAlgorithm::Zstd,
-            _ => CompressionAlgorithm::None,
+/// Registry entry describing how a codec is identified in files
+struct CodecInfo {
+    algorithm: CompressionAlgorithm,
+    name: &'static str,
+    /// Value of the two compression bits in the flags byte. Only the original
+    /// v3 codecs have one; files using any other codec set the bits to 00 and
+    /// name the codec in the metadata `codec` key instead.
+    flag_bits: Option<u8>,
+}
+
+/// Every codec this build can read and write, in `--auto` trial order
+const CODECS: &[CodecInfo] = &[
+    CodecInfo { algorithm: CompressionAlgorithm::None, name: "none", flag_bits: Some(0) },
+    CodecInfo { algorithm: CompressionAlgorithm::Zlib, name: "zlib", flag_bits: Some(1) },
+    CodecInfo { algorithm: CompressionAlgorithm::Brotli, name: "brotli", flag_bits: Some(2) },
+    CodecInfo { algorithm: CompressionAlgorithm::Zstd, name: "zstd", flag_bits: Some(3) },
+    CodecInfo { algorithm: CompressionAlgorithm::Lz4, name: "lz4", flag_bits: None },
+    CodecInfo { algorithm: CompressionAlgorithm::Lzma, name: "lzma", flag_bits: None },
+];
+
+impl CompressionAlgorithm {
+    fn info(self) -> &'static CodecInfo {
+        CODECS.iter().find(|codec| codec.algorithm == self).expect("codec missing from CODECS")
+    }
+    
+    fn name(self) -> &'static str {
+        self.info().name
+    }
+    
+    /// Look up a codec by its registry name (`xz` is accepted for lzma)
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let name = if name == "xz" { "lzma" } else { name.as_str() };
+        CODECS.iter().find(|codec| codec.name == name).map(|codec| codec.algorithm)
+    }
+    
+    /// Codec named by the two compression bits of the flags byte
+    fn from_flag_bits(bits: u8) -> Self {
+        CODECS.iter()
+            .find(|codec| codec.flag_bits == Some(bits & 0b00000011))
+            .map(|codec| codec.algorithm)
+            .unwrap_or(CompressionAlgorithm::None)
+    }
+    
+    /// Accepted `--level` values (`0..=0` for codecs without levels)
+    fn level_range(self) -> std::ops::RangeInclusive<i32> {
+        match self {
+            Self::None | Self::Lz4 => 0..=0,
+            Self::Zlib | Self::Lzma => 0..=9,
+            Self::Brotli => 0..=11,
+            Self::Zstd => zstd::compression_level_range(),
+        }
+    }
+    
+    fn has_levels(self) -> bool {
+        self.level_range() != (0..=0)
+    }
+    
+    /// Level used without `--level` (best compression, except zstd's
+    /// slow 20-22 "ultra" levels and xz's memory-hungry 7-9)
+    fn default_level(self) -> i32 {
+        match self {
+            Self::None | Self::Lz4 => 0,
+            Self::Zlib => 9,
+            Self::Brotli => 11,
+            Self::Zstd => 19,
+            Self::Lzma => 6,
         }
     }
 }
 
 // ============================================================================
-// ICC Profile Support
+// Zstd Dictionaries
 // ============================================================================
 
-use lcms2::*;
+/// Dictionary size `train-dict` aims for (zstd's own default)
+const DEFAULT_DICTIONARY_SIZE: usize = 112_640;
+
+/// Metadata reference to the zstd dictionary a file's zstd data was compressed
+/// with; `embedded` files carry the dictionary right after the metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct DictionaryRef {
+    id: u32,
+    embedded: bool,
+}
 
-fn extract_icc_profile(path: &PathBuf) -> Option<Vec<u8>> {
-    // Try to extract ICC profile from PNG
-    if let Ok(file) = std::fs::File::open(path) {
-        let decoder = png::Decoder::new(file);
-        if let Ok(reader) = decoder.read_info() {
-            if let Some(icc_profile) = reader.info().icc_profile.clone() {
-                println!("[INFO] Extracted ICC profile: {} bytes", icc_profile.len());
-                return Some(icc_profile.to_vec());
+/// A loaded zstd dictionary and, when writing, whether to embed it
+#[derive(Debug, Clone)]
+struct ZstdDictionary {
+    id: u32,
+    data: Arc<Vec<u8>>,
+    embed: bool,
+}
+
+impl ZstdDictionary {
+    /// Wrap trained dictionary bytes, reading the ID zstd stores in them
+    fn from_bytes(data: Vec<u8>) -> Result<Self, std::io::Error> {
+        let id = zstd::zstd_safe::get_dict_id_from_dict(&data)
+            .ok_or_else(|| std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Not a zstd dictionary"
+            ))?
+            .get();
+        Ok(Self { id, data: Arc::new(data), embed: false })
+    }
+}
+
+/// Load a dictionary file (e.g. from `--dict`)
+fn load_dictionary(path: &Path) -> Result<ZstdDictionary, std::io::Error> {
+    ZstdDictionary::from_bytes(fs::read(path)?)
+}
+
+/// Find a dictionary by ID: the one given on the command line, then the
+/// files and directories (`*.dict`) listed in `YEET_DICT_PATH`
+fn find_dictionary(id: u32, given: Option<&ZstdDictionary>) -> Option<ZstdDictionary> {
+    if let Some(dictionary) = given.filter(|d| d.id == id) {
+        return Some(ZstdDictionary { embed: false, ..dictionary.clone() });
+    }
+    
+    let search_path = env::var_os("YEET_DICT_PATH")?;
+    for entry in env::split_paths(&search_path) {
+        let candidates: Vec<PathBuf> = if entry.is_dir() {
+            fs::read_dir(&entry)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "dict"))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            vec![entry]
+        };
+        for path in candidates {
+            if let Ok(dictionary) = load_dictionary(&path) {
+                if dictionary.id == id {
+                    return Some(dictionary);
+                }
             }
         }
     }
     None
 }
 
-fn apply_icc_profile(data: &mut Vec<u8>, profile_data: &[u8], width: u32, height: u32, has_alpha: bool) {
-    // Create ICC profile from data
-    let profile = match Profile::new_icc(profile_data) {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("[WARN] Failed to load ICC profile: {:?}", e);
-            return;
-        }
+/// Dictionary data for a zstd frame, which records the ID of the dictionary
+/// it was compressed with (if any)
+fn frame_dictionary<'a>(data: &[u8], dictionary: Option<&'a ZstdDictionary>) -> Result<Option<&'a [u8]>, std::io::Error> {
+    let Some(id) = zstd::zstd_safe::get_dict_id_from_frame(data) else {
+        return Ok(None);
     };
-    
-    // Get sRGB profile for display
-    let srgb_profile = Profile::new_srgb();
-    
-    // Create transform
-    let pixel_format = if has_alpha { 
-        PixelFormat::RGBA_8 
-    } else { 
-        PixelFormat::RGB_8 
-    };
-    
-    let transform = match Transform::new(
-        &profile,
-        pixel_format,
-        &srgb_profile,
-        pixel_format,
-        Intent::Perceptual,
-    ) {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("[WARN] Failed to create color transform: {:?}", e);
-            return;
-        }
-    };
-    
-    // Apply transform to image data
-    let pixels = (width * height) as usize;
-    let bytes_per_pixel = if has_alpha { 4 } else { 3 };
-    
-    if data.len() != pixels * bytes_per_pixel {
-        eprintln!("[WARN] Data size mismatch for ICC transform");
-        return;
-    }
-    
-    // Process in chunks for better performance
-    let chunk_size = 1024; // Process 1024 pixels at a time
-    for chunk_start in (0..pixels).step_by(chunk_size) {
-        let chunk_end = (chunk_start + chunk_size).min(pixels);
-        let byte_start = chunk_start * bytes_per_pixel;
-        let byte_end = chunk_end * bytes_per_pixel;
-        
-        let chunk = &mut data[byte_start..byte_end];
-        transform.transform_pixels(chunk, chunk);
+    match dictionary {
+        Some(dictionary) if dictionary.id == id.get() => Ok(Some(dictionary.data.as_slice())),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Missing zstd dictionary {}", id)
+        )),
     }
-    
-    println!("[INFO] Applied ICC color correction");
 }
 
 // ============================================================================
-// v3 Conversion (Placeholder Implementation)
+// ICC Profile Support
 // ============================================================================
 
-fn png_to_yeet_v3(
-    path: PathBuf,
-    compress: CompressionAlgorithm,
-    use_binary: bool,
-) -> Result<(), std::io::Error> {
-    println!("[INFO] v3 format is experimental");
-    
-    let img = image::open(&path).expect("File not found!");
-    let width = img.width();
-    let height = img.height();
-    
-    let has_alpha = matches!(
-        img.color(),
-        image::ColorType::Rgba8 | image::ColorType::Rgba16
-    );
+use lcms2::*;
+
+/// Names accepted by `--profile` and the profile commands besides `.icc` paths
+const BUILTIN_PROFILES: &[&str] = &["sRGB", "Display P3", "Adobe RGB", "ProPhoto"];
+
+/// Extract the ICC profile of a PNG, synthesizing one from its sRGB/gAMA/cHRM
+/// chunks when there is no iCCP chunk
+///
+/// For synthesized profiles the second value is the color space label to record
+/// in the metadata.
+fn extract_icc_profile(path: &Path) -> Option<(Vec<u8>, Option<String>)> {
+    let file = std::fs::File::open(path).ok()?;
+    let decoder = png::Decoder::new(file);
+    let reader = decoder.read_info().ok()?;
+    let info = reader.info();
     
-    // Extract ICC profile (TODO)
-    let icc_profile = extract_icc_profile(&path);
+    if let Some(icc_profile) = info.icc_profile.clone() {
+        return Some((icc_profile.to_vec(), None));
+    }
     
-    // Prepare metadata
-    let mut metadata = YeetMetadataV3::default();
-    metadata.frame_count = 1;
-    if icc_profile.is_some() {
-        metadata.color_profile = Some("embedded".to_string());
+    let (profile, color_space) = synthesize_png_profile(info)?;
+    match profile.icc() {
+        Ok(icc_profile) => Some((icc_profile, Some(color_space))),
+        Err(e) => {
+            eprintln!("[WARN] Failed to synthesize ICC profile: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Build a profile equivalent to a PNG's sRGB chunk, or to its gAMA/cHRM pair
+///
+/// Follows the PNG precedence rules: sRGB overrides gAMA and cHRM, and a
+/// missing gAMA or cHRM falls back to the sRGB curve or primaries.
+fn synthesize_png_profile(info: &png::Info) -> Option<(Profile, String)> {
+    if info.srgb.is_some() {
+        return Some((Profile::new_srgb(), "sRGB".to_string()));
     }
     
-    let metadata_json = serde_json::to_string(&metadata).unwrap();
-    let metadata_bytes = metadata_json.as_bytes();
+    let gamma = info.source_gamma
+        .map(|g| g.into_value() as f64)
+        .filter(|g| *g > 0.0);
+    let chromaticities = info.source_chromaticities;
+    if gamma.is_none() && chromaticities.is_none() {
+        return None;
+    }
     
-    // Encode pixel data
-    let pixel_data = if use_binary {
-        let bytes_per_pixel = if has_alpha { 4 } else { 3 };
-        let mut data = Vec::with_capacity((width * height * bytes_per_pixel) as usize);
-        
-        for pixel in img.pixels() {
-            data.push(pixel.2[0]);
-            data.push(pixel.2[1]);
-            data.push(pixel.2[2]);
-            if has_alpha {
-                data.push(pixel.2[3]);
-            }
-        }
-        data
-    } else {
-        let chars_per_pixel = if has_alpha { 8 } else { 6 };
-        let mut str = String::with_capacity((width * height * chars_per_pixel) as usize);
-        
-        for pixel in img.pixels() {
-            str.push_str(&format!("{:02X}{:02X}{:02X}", pixel.2[0], pixel.2[1], pixel.2[2]));
-            if has_alpha {
-                str.push_str(&format!("{:02X}", pixel.2[3]));
-            }
-        }
-        str.into_bytes()
+    let xy = |(x, y): (png::ScaledFloat, png::ScaledFloat)| CIExyY {
+        x: x.into_value() as f64,
+        y: y.into_value() as f64,
+        Y: 1.0,
+    };
+    let (white_point, primaries) = match chromaticities {
+        Some(c) => (xy(c.white), CIExyYTRIPLE { Red: xy(c.red), Green: xy(c.green), Blue: xy(c.blue) }),
+        None => (
+            CIExyY { x: 0.3127, y: 0.3290, Y: 1.0 },
+            CIExyYTRIPLE {
+                Red: CIExyY { x: 0.640, y: 0.330, Y: 1.0 },
+                Green: CIExyY { x: 0.300, y: 0.600, Y: 1.0 },
+                Blue: CIExyY { x: 0.150, y: 0.060, Y: 1.0 },
+            },
+        ),
     };
     
-    // Compress
-    let compressed_data = compress_data(&pixel_data, compress);
+    // gAMA stores the encoding exponent, the profile needs the decoding one
+    let curve = match gamma {
+        Some(g) => ToneCurve::new(1.0 / g),
+        None => srgb_tone_curve().ok()?,
+    };
     
-    // Build flags
-    let mut flags: u8 = (compress as u8) & 0b00000011;
-    if has_alpha { flags |= 0b00000100; }
-    if use_binary { flags |= 0b00001000; }
-    if icc_profile.is_some() { flags |= 0b00100000; }
-    
-    // Write file
-    if let Some(path_str) = path.to_str() {
-        let output_path = path_str.replace(".png", ".yeet");
-        let mut file = File::create(&output_path)?;
-        
-        // Header
-        file.write_all(b"YEET")?;
-        file.write_all(&[3])?; // Version 3
-        file.write_all(&[flags])?;
-        file.write_all(&width.to_le_bytes())?;
-        file.write_all(&height.to_le_bytes())?;
-        file.write_all(&1u32.to_le_bytes())?; // Frame count
-        file.write_all(&0u32.to_le_bytes())?; // Loop count
-        
-        // Metadata
-        file.write_all(&(metadata_bytes.len() as u32).to_le_bytes())?;
-        file.write_all(metadata_bytes)?;
-        
-        // ICC profile
-        if let Some(ref profile) = icc_profile {
-            file.write_all(&(profile.len() as u32).to_le_bytes())?;
-            file.write_all(profile)?;
-        } else {
-            file.write_all(&0u32.to_le_bytes())?;
-        }
-        
-        // Frame data
-        file.write_all(&0u32.to_le_bytes())?; // Frame delay
-        file.write_all(&(compressed_data.len() as u32).to_le_bytes())?;
-        file.write_all(&compressed_data)?;
-        
-        file.flush()?;
-        
-        println!("[OK] Converted to YEET v3: {}", output_path);
-        println!("  Dimensions: {}x{}", width, height);
-        println!("  Format: v3 (experimental)");
-        println!("  ICC Profile: {}", icc_profile.is_some());
+    let mut label = match gamma {
+        Some(g) => format!("RGB gamma {:.2}", 1.0 / g),
+        None => "RGB sRGB curve".to_string(),
+    };
+    if chromaticities.is_some() {
+        label.push_str(", cHRM primaries");
     }
     
-    Ok(())
+    let mut profile = Profile::new_rgb(&white_point, &primaries, &[&curve, &curve, &curve]).ok()?;
+    set_profile_description(&mut profile, &format!("PNG {}", label));
+    Some((profile, label))
 }
 
-// ============================================================================
-// v3 Reading & Viewing
-// ============================================================================
+/// The sRGB transfer function as an lcms2 parametric curve
+fn srgb_tone_curve() -> LCMSResult<ToneCurve> {
+    ToneCurve::new_parametric(4, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045])
+}
+
+fn set_profile_description(profile: &mut Profile, description: &str) {
+    let mut mlu = MLU::new(1);
+    mlu.set_text_ascii(description, Locale::none());
+    profile.write_tag(TagSignature::ProfileDescriptionTag, Tag::MLU(&mlu));
+}
 
-fn read_yeet_v3(path: PathBuf) -> Result<YeetImageV3, std::io::Error> {
-    let mut file = File::open(&path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
+/// Build one of the built-in RGB profiles (name matching ignores case and separators)
+fn builtin_profile(name: &str) -> Option<Profile> {
+    let key: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    
+    let d65 = CIExyY { x: 0.3127, y: 0.3290, Y: 1.0 };
+    let d50 = CIExyY { x: 0.3457, y: 0.3585, Y: 1.0 };
+    
+    let (description, white_point, primaries, curve) = match key.as_str() {
+        "srgb" => return Some(Profile::new_srgb()),
+        "displayp3" | "p3" => (
+            "Display P3",
+            d65,
+            [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            srgb_tone_curve().ok()?,
+        ),
+        "adobergb" | "adobergb1998" => (
+            "Adobe RGB (1998)",
+            d65,
+            [(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)],
+            ToneCurve::new(563.0 / 256.0),
+        ),
+        "prophoto" | "prophotorgb" | "rommrgb" => (
+            "ProPhoto RGB",
+            d50,
+            [(0.7347, 0.2653), (0.1596, 0.8404), (0.0366, 0.0001)],
+            ToneCurve::new(1.8),
+        ),
+        _ => return None,
+    };
     
-    let mut pos = 0;
+    let [r, g, b] = primaries.map(|(x, y)| CIExyY { x, y, Y: 1.0 });
+    let primaries = CIExyYTRIPLE { Red: r, Green: g, Blue: b };
+    let mut profile = Profile::new_rgb(&white_point, &primaries, &[&curve, &curve, &curve]).ok()?;
+    set_profile_description(&mut profile, description);
+    Some(profile)
+}
+
+/// Resolve a profile argument (built-in name or path to an `.icc` file) to ICC bytes
+fn load_profile_arg(arg: &str) -> Result<Vec<u8>, std::io::Error> {
+    if let Some(profile) = builtin_profile(arg) {
+        return profile.icc().map_err(|e| std::io::Error::other(
+            format!("Failed to serialize built-in profile {}: {:?}", arg, e)
+        ));
+    }
     
-    // Verify magic bytes
-    if &buffer[pos..pos+4] != b"YEET" {
+    let path = Path::new(arg);
+    if !path.exists() {
         return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            "Invalid YEET file: wrong magic bytes"
+            std::io::ErrorKind::NotFound,
+            format!("Unknown profile '{}' (built-in: {})", arg, BUILTIN_PROFILES.join(", "))
         ));
     }
-    pos += 4;
     
-    // Read version
-    let version = buffer[pos];
-    pos += 1;
-    if version != 3 {
+    let data = fs::read(path)?;
+    if let Err(e) = Profile::new_icc(&data) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
-            format!("Expected v3, got v{}", version)
+            format!("Not a valid ICC profile: {:?} ({:?})", path, e)
         ));
     }
+    Ok(data)
+}
+
+/// ICC rendering intents selectable from the CLI and the viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenderingIntent {
+    Perceptual,
+    Relative,
+    Saturation,
+    Absolute,
+}
+
+impl RenderingIntent {
+    const ALL: [RenderingIntent; 4] = [
+        RenderingIntent::Perceptual,
+        RenderingIntent::Relative,
+        RenderingIntent::Saturation,
+        RenderingIntent::Absolute,
+    ];
     
-    // Read flags
-    let flags = buffer[pos];
-    pos += 1;
-    
-    let compression = CompressionAlgorithm::from(flags & 0b00000011);
-    let has_alpha = (flags & 0b00000100) != 0;
-    let is_binary = (flags & 0b00001000) != 0;
-    let is_animated = (flags & 0b00010000) != 0;
-    let has_icc = (flags & 0b00100000) != 0;
-    let is_hdr = (flags & 0b01000000) != 0;
-    
-    // Read dimensions
-    let width = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]);
-    pos += 4;
-    let height = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]);
-    pos += 4;
-    
-    // Read frame count
-    let frame_count = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]);
-    pos += 4;
-    
-    // Read loop count
-    let loop_count = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]);
-    pos += 4;
-    
-    // Read metadata
-    let metadata_len = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]) as usize;
-    pos += 4;
-    
-    let metadata_str = String::from_utf8_lossy(&buffer[pos..pos+metadata_len]);
-    let metadata: YeetMetadataV3 = serde_json::from_str(&metadata_str)
-        .unwrap_or_else(|_| YeetMetadataV3::default());
-    pos += metadata_len;
-    
-    // Read ICC profile if present
-    let icc_profile = if has_icc {
-        let icc_len = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]) as usize;
-        pos += 4;
-        let profile = buffer[pos..pos+icc_len].to_vec();
-        pos += icc_len;
-        Some(profile)
-    } else {
-        let icc_len = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]) as usize;
-        pos += 4;
-        if icc_len > 0 {
-            eprintln!("[WARN] ICC data present but flag not set");
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "perceptual" => Some(RenderingIntent::Perceptual),
+            "relative" | "relative-colorimetric" => Some(RenderingIntent::Relative),
+            "saturation" => Some(RenderingIntent::Saturation),
+            "absolute" | "absolute-colorimetric" => Some(RenderingIntent::Absolute),
+            _ => None,
         }
-        None
-    };
-    
-    // Read frames
-    let mut frames = Vec::new();
-    for _ in 0..frame_count {
-        // Frame delay
-        let delay = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]);
-        pos += 4;
-        
-        // Frame data length
-        let data_len = u32::from_le_bytes([buffer[pos], buffer[pos+1], buffer[pos+2], buffer[pos+3]]) as usize;
-        pos += 4;
-        
-        // Frame data
-        let compressed_data = &buffer[pos..pos+data_len];
-        let data = decompress_data(compressed_data, compression);
-        pos += data_len;
-        
-        frames.push(YeetFrame { delay, data });
     }
     
-    println!("[INFO] Loaded YEET v3: {}x{}, {} frames", width, height, frame_count);
-    if has_icc {
-        println!("[INFO] ICC profile present");
+    fn name(self) -> &'static str {
+        match self {
+            RenderingIntent::Perceptual => "perceptual",
+            RenderingIntent::Relative => "relative",
+            RenderingIntent::Saturation => "saturation",
+            RenderingIntent::Absolute => "absolute",
+        }
     }
     
-    Ok(YeetImageV3 {
-        width,
-        height,
-        has_alpha,
-        is_hdr,
-        metadata,
-        icc_profile,
-        frames,
-    })
+    fn to_lcms(self) -> Intent {
+        match self {
+            RenderingIntent::Perceptual => Intent::Perceptual,
+            RenderingIntent::Relative => Intent::RelativeColorimetric,
+            RenderingIntent::Saturation => Intent::Saturation,
+            RenderingIntent::Absolute => Intent::AbsoluteColorimetric,
+        }
+    }
 }
 
-fn yeet_v3_to_image(yeet_img: &YeetImageV3, frame_index: usize) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
-    let frame = &yeet_img.frames[frame_index];
-    let mut data = frame.data.clone();
-    
-    // Apply ICC profile if present
-    if let Some(ref profile) = yeet_img.icc_profile {
-        apply_icc_profile(&mut data, profile, yeet_img.width, yeet_img.height, yeet_img.has_alpha);
+/// Rendering intent and black point compensation used for every ICC transform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColorTransformOptions {
+    intent: RenderingIntent,
+    black_point_compensation: bool,
+}
+
+impl Default for ColorTransformOptions {
+    fn default() -> Self {
+        Self {
+            intent: RenderingIntent::Perceptual,
+            black_point_compensation: false,
+        }
+    }
+}
+
+impl ColorTransformOptions {
+    /// Parse `--intent <name>` and `--bpc` from the command line
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let intent = match arg_value(args, "--intent") {
+            Some(name) => RenderingIntent::from_name(name).ok_or_else(|| format!(
+                "Unknown rendering intent '{}' (expected perceptual, relative, saturation or absolute)",
+                name
+            ))?,
+            None => RenderingIntent::Perceptual,
+        };
+        
+        Ok(Self {
+            intent,
+            black_point_compensation: args.contains(&"--bpc".to_string()),
+        })
     }
     
-    let mut img: ImageBuffer<Rgba<u8>, Vec<u8>> = 
-        ImageBuffer::new(yeet_img.width, yeet_img.height);
-    
-    let bytes_per_pixel = if yeet_img.has_alpha { 4 } else { 3 };
-    
-    for y in 0..yeet_img.height {
-        for x in 0..yeet_img.width {
-            let idx = ((y * yeet_img.width + x) * bytes_per_pixel) as usize;
-            
-            let r = data[idx];
-            let g = data[idx + 1];
-            let b = data[idx + 2];
-            let a = if yeet_img.has_alpha { data[idx + 3] } else { 255 };
-            
-            img.put_pixel(x, y, Rgba([r, g, b, a]));
+    fn flags(self) -> Flags {
+        if self.black_point_compensation {
+            Flags::BLACKPOINT_COMPENSATION
+        } else {
+            Flags::default()
         }
     }
+}
+
+/// lcms2 pixel format of a raw payload (16-bit samples are stored big-endian)
+fn icc_pixel_format(color_model: ColorModel, has_alpha: bool, is_hdr: bool) -> PixelFormat {
+    let base = match (color_model, is_hdr) {
+        (ColorModel::Rgb, false) => PixelFormat::RGB_8,
+        (ColorModel::Rgb, true) => PixelFormat::RGB_16,
+        (ColorModel::Cmyk, false) => PixelFormat::CMYK_8,
+        (ColorModel::Cmyk, true) => PixelFormat::CMYK_16,
+        (ColorModel::Lab, false) => PixelFormat::Lab_8,
+        (ColorModel::Lab, true) => PixelFormat::Lab_16,
+    };
     
-    img
+    // lcms2 only names a few of the alpha / byte-swapped variants, so set the
+    // EXTRA (bit 7) and ENDIAN16 (bit 11) fields of the format directly
+    let mut bits = base.0;
+    if has_alpha {
+        bits |= 1 << 7;
+    }
+    if is_hdr && cfg!(target_endian = "little") {
+        bits |= 1 << 11;
+    }
+    PixelFormat(bits)
 }
 
-// ============================================================================
-// GUI Application with Animation Support
-// ============================================================================
+/// White point of Lab pixel data (D50, the ICC connection space white)
+const LAB_WHITE_POINT: CIExyY = CIExyY { x: 0.3457, y: 0.3585, Y: 1.0 };
 
-struct YeetV3ViewerApp {
-    image: Option<YeetImageV3>,
-    current_frame: usize,
-    last_frame_time: std::time::Instant,
-    playing: bool,
-    loaded_image: Option<RetainedImage>,
+/// Color model of the pixels an ICC profile describes
+fn profile_color_model(profile_data: &[u8]) -> Option<ColorModel> {
+    match Profile::new_icc(profile_data).ok()?.color_space() {
+        ColorSpaceSignature::RgbData => Some(ColorModel::Rgb),
+        ColorSpaceSignature::CmykData => Some(ColorModel::Cmyk),
+        ColorSpaceSignature::LabData => Some(ColorModel::Lab),
+        _ => None,
+    }
 }
 
-impl YeetV3ViewerApp {
-    fn new(image: YeetImageV3) -> Self {
-        let is_animated = image.frames.len() > 1;
-        Self {
-            image: Some(image),
-            current_frame: 0,
-            last_frame_time: std::time::Instant::now(),
-            playing: is_animated,
-            loaded_image: None,
-        }
+/// Fail unless `profile_data` describes pixels of `color_model`
+fn check_profile_color_model(profile_data: &[u8], color_model: ColorModel) -> Result<(), std::io::Error> {
+    match profile_color_model(profile_data) {
+        Some(model) if model == color_model => Ok(()),
+        model => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} profile cannot be used for {} pixels",
+                model.map_or("Unsupported", ColorModel::name),
+                color_model.name()
+            )
+        )),
     }
+}
+
+/// CIELAB identity profile (D50) used for Lab pixel data
+fn lab_profile_icc() -> Result<Vec<u8>, std::io::Error> {
+    Profile::new_lab4_context(GlobalContext::new(), &LAB_WHITE_POINT)
+        .and_then(|profile| profile.icc())
+        .map_err(|e| std::io::Error::other(format!("Failed to create Lab profile: {:?}", e)))
+}
+
+/// Convert raw pixels from one ICC profile (and pixel format) to another
+fn convert_icc_pixels(
+    data: &[u8],
+    from_profile: &[u8],
+    from_format: PixelFormat,
+    to_profile: &[u8],
+    to_format: PixelFormat,
+    options: ColorTransformOptions,
+) -> Result<Vec<u8>, std::io::Error> {
+    let invalid = |what: &str, e: lcms2::Error| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{}: {:?}", what, e)
+    );
     
-    fn update_current_frame(&mut self) {
-        if let Some(ref img) = self.image {
-            let frame_img = yeet_v3_to_image(img, self.current_frame);
-            
-            // Save to temp file for RetainedImage
-            frame_img.save(TEMP_RESULT_PATH).ok();
-            
-            if let Ok(retained) = RetainedImage::from_image_bytes(
-                "temp_frame",
-                &std::fs::read(TEMP_RESULT_PATH).unwrap()
-            ) {
-                self.loaded_image = Some(retained);
-            }
-        }
-    }
+    let source = Profile::new_icc(from_profile).map_err(|e| invalid("Failed to load source profile", e))?;
+    let target = Profile::new_icc(to_profile).map_err(|e| invalid("Failed to load target profile", e))?;
+    
+    let transform: Transform<u8, u8> = Transform::new_flags(
+        &source,
+        from_format,
+        &target,
+        to_format,
+        options.intent.to_lcms(),
+        options.flags() | Flags::COPY_ALPHA,
+    ).map_err(|e| invalid("Failed to create color transform", e))?;
+    
+    let pixel_count = data.len() / from_format.bytes_per_pixel();
+    let mut output = vec![0u8; pixel_count * to_format.bytes_per_pixel()];
+    transform.transform_pixels(&data[..pixel_count * from_format.bytes_per_pixel()], &mut output);
+    Ok(output)
 }
 
-impl eframe::App for YeetV3ViewerApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle animation
-        if let Some(ref img) = self.image {
-            if self.playing && img.frames.len() > 1 {
-                let current_delay = img.frames[self.current_frame].delay;
-                let delay_ms = if current_delay > 0 { current_delay } else { 100 }; // Default 100ms
-                
-                if self.last_frame_time.elapsed().as_millis() >= delay_ms as u128 {
-                    self.current_frame = (self.current_frame + 1) % img.frames.len();
-                    self.update_current_frame();
-                    self.last_frame_time = std::time::Instant::now();
-                }
-                
-                // Request repaint for smooth animation
-                ctx.request_repaint();
-            }
+/// Output device simulated by the viewer's soft-proofing mode
+#[derive(Debug, Clone, PartialEq)]
+struct ProofingSettings {
+    name: String,
+    profile: Vec<u8>,
+    gamut_warning: bool,
+}
+
+/// Everything the display transform depends on
+#[derive(Debug, Clone, PartialEq)]
+struct DisplaySettings {
+    color: ColorTransformOptions,
+    proofing: Option<ProofingSettings>,
+}
+
+/// Out-of-gamut pixels are painted magenta when the gamut warning is on
+const GAMUT_ALARM_COLOR: [u16; 3] = [0xFFFF, 0x0000, 0xFFFF];
+
+/// Embedded profile → sRGB transform, built once per image and reused for every frame
+///
+/// Uses its own lcms2 context so the gamut alarm color doesn't leak into other transforms.
+/// The output is always RGB(A) with the same sample size as the stored pixels.
+struct DisplayTransform {
+    input_bytes_per_pixel: usize,
+    output_bytes_per_pixel: usize,
+    transform: Transform<u8, u8, ThreadContext>,
+    // Declared after `transform` so it is dropped last
+    _context: ThreadContext,
+}
+
+impl DisplayTransform {
+    /// Returns `None` when there is nothing to correct: RGB pixels with no (usable)
+    /// embedded profile and no soft-proofing. Untagged RGB images are proofed as
+    /// sRGB and untagged Lab images use the D50 Lab identity profile.
+    fn new(yeet_img: &YeetImageV3, settings: &DisplaySettings) -> Option<Self> {
+        let color_model = yeet_img.metadata.color_model;
+        if yeet_img.icc_profile.is_none() && settings.proofing.is_none() && color_model == ColorModel::Rgb {
+            return None;
         }
         
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if let Some(ref img) = self.image {
-                // Info panel
-                ui.horizontal(|ui| {
-                    ui.heading("YEET v3 Viewer");
-                    ui.separator();
-                    ui.label(format!("{}x{}", img.width, img.height));
-                    
-                    if img.frames.len() > 1 {
-                        ui.separator();
-                        ui.label(format!("Frame {}/{}", self.current_frame + 1, img.frames.len()));
-                        
-                        if ui.button(if self.playing { "⏸ Pause" } else { "▶ Play" }).clicked() {
-                            self.playing = !self.playing;
-                        }
-                        
-                        if ui.button("⏮ Prev").clicked() {
-                            self.current_frame = if self.current_frame == 0 {
-                                img.frames.len() - 1
-                            } else {
-                                self.current_frame - 1
-                            };
-                            self.update_current_frame();
-                        }
-                        
-                        if ui.button("⏭ Next").clicked() {
-                            self.current_frame = (self.current_frame + 1) % img.frames.len();
-                            self.update_current_frame();
+        let mut context = ThreadContext::new();
+        
+        // Create ICC profile from data
+        let profile = match yeet_img.icc_profile {
+            Some(ref profile_data) => match Profile::new_icc_context(&context, profile_data) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("[WARN] Failed to load ICC profile: {:?}", e);
+                    return None;
+                }
+            },
+            None => match color_model {
+                ColorModel::Rgb => Profile::new_srgb_context(&context),
+                ColorModel::Lab => {
+                    match Profile::new_lab4_context(&context, &LAB_WHITE_POINT) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("[WARN] Failed to create Lab profile: {:?}", e);
+                            return None;
                         }
                     }
-                    
-                    if img.icc_profile.is_some() {
-                        ui.separator();
-                        ui.label("🎨 ICC Profile");
+                }
+                ColorModel::Cmyk => {
+                    eprintln!("[WARN] CMYK image has no ICC profile, cannot convert for display");
+                    return None;
+                }
+            },
+        };
+        
+        // Get sRGB profile for display
+        let srgb_profile = Profile::new_srgb_context(&context);
+        
+        let input_format = icc_pixel_format(color_model, yeet_img.has_alpha, yeet_img.is_hdr);
+        let output_format = icc_pixel_format(ColorModel::Rgb, yeet_img.has_alpha, yeet_img.is_hdr);
+        let options = settings.color;
+        let transform = match settings.proofing {
+            Some(ref proofing) => {
+                let proof_profile = match Profile::new_icc_context(&context, &proofing.profile) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("[WARN] Failed to load proofing profile: {:?}", e);
+                        return None;
                     }
-                });
-                
-                ui.separator();
-                
-                // Metadata panel (collapsible)
-                ui.collapsing("📊 Metadata", |ui| {
-                    egui::Grid::new("metadata_grid")
-                        .num_columns(2)
-                        .spacing([10.0, 5.0])
-                        .show(ui, |ui| {
-                            if let Some(ref author) = img.metadata.author {
-                                ui.label("Author:");
-                                ui.label(author);
-                                ui.end_row();
-                            }
-                            if let Some(ref created) = img.metadata.created {
-                                ui.label("Created:");
-                                ui.label(created);
-                                ui.end_row();
-                            }
-                            ui.label("Software:");
-                            ui.label(&img.metadata.software);
-                            ui.end_row();
-                            
-                            if let Some(ref color_space) = img.metadata.color_space {
-                                ui.label("Color Space:");
-                                ui.label(color_space);
-                                ui.end_row();
-                            }
-                            
-                            ui.label("Bit Depth:");
-                            ui.label(format!("{}-bit", img.metadata.bit_depth));
-                            ui.end_row();
-                            
-                            if img.frames.len() > 1 {
-                                ui.label("Total Frames:");
-                                ui.label(format!("{}", img.frames.len()));
-                                ui.end_row();
-                                
-                                if let Some(delay) = img.metadata.frame_delay {
-                                    ui.label("Frame Delay:");
-                                    ui.label(format!("{}ms", delay));
-                                    ui.end_row();
-                                }
-                            }
-                        });
-                });
+                };
                 
-                ui.separator();
+                let mut flags = options.flags() | Flags::COPY_ALPHA | Flags::SOFT_PROOFING;
+                if proofing.gamut_warning {
+                    let mut alarm_codes = [0u16; 16];
+                    alarm_codes[..3].copy_from_slice(&GAMUT_ALARM_COLOR);
+                    context.set_alarm_codes(alarm_codes);
+                    flags = flags | Flags::GAMUT_CHECK;
+                }
                 
-                // Image display
-                egui::ScrollArea::both()
-                    .auto_shrink([false, false])
-                    .show(ui, |ui| {
-                        if self.loaded_image.is_none() {
-                            self.update_current_frame();
-                        }
-                        
-                        if let Some(ref retained_img) = self.loaded_image {
-                            retained_img.show(ui);
-                        }
-                    });
+                Transform::new_proofing_context(
+                    &context,
+                    &profile,
+                    input_format,
+                    &srgb_profile,
+                    output_format,
+                    &proof_profile,
+                    options.intent.to_lcms(),
+                    Intent::RelativeColorimetric,
+                    flags,
+                )
             }
-        });
+            None => Transform::new_flags_context(
+                &context,
+                &profile,
+                input_format,
+                &srgb_profile,
+                output_format,
+                options.intent.to_lcms(),
+                options.flags() | Flags::COPY_ALPHA,
+            ),
+        };
+        
+        let transform = match transform {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("[WARN] Failed to create color transform: {:?}", e);
+                return None;
+            }
+        };
+        
+        Some(Self {
+            input_bytes_per_pixel: input_format.bytes_per_pixel(),
+            output_bytes_per_pixel: output_format.bytes_per_pixel(),
+            transform,
+            _context: context,
+        })
+    }
+    
+    /// Convert one frame of raw pixels to sRGB (`None` if the frame size doesn't fit)
+    fn apply(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if !data.len().is_multiple_of(self.input_bytes_per_pixel) {
+            eprintln!("[WARN] Data size mismatch for ICC transform, skipping color correction");
+            return None;
+        }
+        let pixel_count = data.len() / self.input_bytes_per_pixel;
+        let mut output = vec![0u8; pixel_count * self.output_bytes_per_pixel];
+        self.transform.transform_pixels(data, &mut output);
+        Some(output)
     }
 }
 
 // ============================================================================
-// Main Entry Point
+// v3 Conversion (Placeholder Implementation)
 // ============================================================================
 
-fn main() -> Result<(), eframe::Error> {
-    env_logger::init();
-    
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 2 {
-        print_usage(&args[0]);
-        std::process::exit(1);
+/// Encode raw RGB(A) bytes as uppercase hex text (hex mode payload)
+fn encode_hex_pixels(raw: &[u8]) -> Vec<u8> {
+    hex_string(raw).into_bytes()
+}
+
+/// Uppercase hex digits for arbitrary bytes (pixel payloads, raw EXIF)
+fn hex_string(bytes: &[u8]) -> String {
+    let mut str = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        str.push_str(&format!("{:02X}", byte));
     }
-    
-    println!("[INFO] YEET v3 - Advanced Features Enabled");
-    println!("  ✅ Brotli/Zstd compression");
-    println!("  ✅ ICC color profiles");
-    println!("  ✅ Multi-frame animation");
-    println!();
-    
-    let command = &args[1];
-    
-    match command.as_str() {
-        "compile" => {
-            if args.len() < 3 {
-                eprintln!("[ERROR] No input file");
-                std::process::exit(1);
-            }
-            
-            let path: PathBuf = args[2].clone().into();
-            let compress = if args.contains(&"--brotli".to_string()) {
-                CompressionAlgorithm::Brotli
-            } else if args.contains(&"--zstd".to_string()) {
-                CompressionAlgorithm::Zstd
-            } else if args.contains(&"--compress".to_string()) {
-                CompressionAlgorithm::Zlib
-            } else {
-                CompressionAlgorithm::None
-            };
-            
-            let binary = args.contains(&"--binary".to_string());
-            
-            match png_to_yeet_v3(path, compress, binary) {
-                Ok(()) => println!("[OK] Conversion complete"),
-                Err(e) => eprintln!("[ERROR] {}", e),
-            }
-            Ok(())
-        }
-        "help" | "--help" | "-h" => {
-            print_usage(&args[0]);
-            Ok(())
-        }
-        _ => {
-            // Try to view the file
-            let path: PathBuf = args[1].clone().into();
-            
-            match read_yeet_v3(path.clone()) {
-                Ok(img) => {
-                    let options = eframe::NativeOptions {
-                        viewport: egui::ViewportBuilder::default()
-                            .with_inner_size([1024.0, 768.0])
-                            .with_title(format!("YEET v3 Viewer - {}", 
-                                path.file_name().unwrap_or_default().to_string_lossy())),
-                        ..Default::default()
-                    };
-                    
-                    eframe::run_native(
-                        "YEET v3 Viewer",
-                        options,
-                        Box::new(|_cc| Box::new(YeetV3ViewerApp::new(img))),
-                    )
-                }
-                Err(e) => {
-                    eprintln!("[ERROR] Failed to load file: {}", e);
-                    eprintln!("Tip: Use 'compile' command to convert PNG to YEET v3");
-                    std::process::exit(1);
-                }
-            }
-        }
+    str
+}
+
+/// Decode a hex mode payload back into raw RGB(A) bytes
+fn decode_hex_pixels(hex: &[u8]) -> Vec<u8> {
+    hex.chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Raw RGB(A) bytes of a frame, regardless of hex/binary encoding
+fn frame_raw_pixels(yeet_img: &YeetImageV3, frame_index: usize) -> Vec<u8> {
+    let data = &yeet_img.frames[frame_index].data;
+    if yeet_img.is_binary {
+        data.clone()
+    } else {
+        decode_hex_pixels(data)
     }
 }
 
-fn print_usage(program: &str) {
+/// Options shared by `compile` and friends
+#[derive(Debug, Clone)]
+struct CompileOptions {
+    compression: CompressionAlgorithm,
+    level: Option<i32>,
+    auto: bool,
+    dictionary: Option<ZstdDictionary>,
+    use_binary: bool,
+    hdr: bool,
+    color_model: ColorModel,
+    profile: Option<String>,
+    color: ColorTransformOptions,
+    thumbnail: Option<u32>,
+    tiles: Option<TileLayout>,
+    strip_rows: Option<u32>,
+    pyramid_levels: Option<u32>,
+    interlace: Interlace,
+    quality: Quality,
+    import_exif: bool,
+    keep_exif: bool,
+    apply_orientation: bool,
+    reproducible: bool,
+}
+
+impl CompileOptions {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let compression = if let Some(name) = arg_value(args, "--codec") {
+            CompressionAlgorithm::from_name(name).ok_or_else(|| format!(
+                "Unknown codec '{}' (expected {})",
+                name,
+                CODECS.iter().map(|codec| codec.name).collect::<Vec<_>>().join(", ")
+            ))?
+        } else if args.contains(&"--brotli".to_string()) {
+            CompressionAlgorithm::Brotli
+        } else if args.contains(&"--zstd".to_string()) {
+            CompressionAlgorithm::Zstd
+        } else if args.contains(&"--lz4".to_string()) {
+            CompressionAlgorithm::Lz4
+        } else if args.contains(&"--lzma".to_string()) || args.contains(&"--xz".to_string()) {
+            CompressionAlgorithm::Lzma
+        } else if args.contains(&"--compress".to_string()) {
+            CompressionAlgorithm::Zlib
+        } else {
+            CompressionAlgorithm::None
+        };
+        
+        let auto = args.contains(&"--auto".to_string());
+        let level = match arg_value(args, "--level") {
+            Some(_) if auto => auto_level_arg(args)?,
+            Some(_) if compression == CompressionAlgorithm::None => {
+                return Err("--level needs a compression codec (e.g. --zstd)".to_string());
+            }
+            Some(_) if !compression.has_levels() => {
+                return Err(format!("{} has no compression levels", compression.name()));
+            }
+            Some(level) => match level.parse::<i32>() {
+                Ok(level) if compression.level_range().contains(&level) => Some(level),
+                _ => {
+                    let range = compression.level_range();
+                    return Err(format!(
+                        "Invalid {} level '{}' (expected {} to {})",
+                        compression.name(), level, range.start(), range.end()
+                    ));
+                }
+            },
+            None => None,
+        };
+        
+        let dictionary = match arg_value(args, "--dict") {
+            Some(_) if compression != CompressionAlgorithm::Zstd && !auto => {
+                return Err("--dict needs --zstd (or --auto)".to_string());
+            }
+            Some(path) => Some(ZstdDictionary {
+                embed: args.contains(&"--embed-dict".to_string()),
+                ..load_dictionary(Path::new(path))
+                    .map_err(|e| format!("Failed to load dictionary {}: {}", path, e))?
+            }),
+            None if args.contains(&"--embed-dict".to_string()) => {
+                return Err("--embed-dict needs --dict <file>".to_string());
+            }
+            None => None,
+        };
+        
+        let color_model = match arg_value(args, "--color-model") {
+            Some(name) => ColorModel::from_name(name).ok_or_else(|| format!(
+                "Unknown color model '{}' (expected rgb, cmyk or lab)",
+                name
+            ))?,
+            None => ColorModel::Rgb,
+        };
+        
+        let thumbnail = match arg_value(args, "--thumbnail-size") {
+            Some(size) => match size.parse::<u32>() {
+                Ok(size) if size > 0 => Some(size),
+                _ => return Err(format!("Invalid thumbnail size '{}'", size)),
+            },
+            None if args.contains(&"--thumbnail".to_string()) => Some(THUMBNAIL_MAX_EDGE),
+            None => None,
+        };
+        
+        let tiles = match arg_value(args, "--tile-size") {
+            Some(size) => Some(TileLayout::parse(size).ok_or_else(|| format!(
+                "Invalid tile size '{}' (expected e.g. 256 or 512x256)",
+                size
+            ))?),
+            None => None,
+        };
+        
+        // Strips are full-width tiles; the width is only known once the image is loaded.
+        // They are the default layout unless the image is tiled or interlaced.
+        let interlaced = args.contains(&"--interlace".to_string());
+        let no_strips = args.contains(&"--no-strips".to_string());
+        let strip_rows = match arg_value(args, "--strip-rows") {
+            Some(_) if no_strips => {
+                return Err("--strip-rows cannot be combined with --no-strips".to_string());
+            }
+            Some(rows) => match rows.parse::<u32>() {
+                Ok(rows) if rows > 0 => Some(rows),
+                _ => return Err(format!("Invalid strip row count '{}'", rows)),
+            },
+            None if args.contains(&"--strips".to_string()) => {
+                if no_strips {
+                    return Err("--strips cannot be combined with --no-strips".to_string());
+                }
+                Some(DEFAULT_STRIP_ROWS)
+            }
+            None if no_strips || tiles.is_some() || interlaced => None,
+            None => Some(DEFAULT_STRIP_ROWS),
+        };
+        if strip_rows.is_some() && tiles.is_some() {
+            return Err("--strips cannot be combined with --tile-size".to_string());
+        }
+        
+        let interlace = if interlaced {
+            if tiles.is_some() || strip_rows.is_some() {
+                return Err("--interlace cannot be combined with --tile-size or --strips".to_string());
+            }
+            Interlace::Adam7
+        } else {
+            Interlace::None
+        };
+        
+        // `None` with --pyramid: pick the level count from the image size
+        let pyramid_levels = match arg_value(args, "--pyramid-levels") {
+            Some(levels) => Some(levels.parse::<u32>().map_err(|_| format!(
+                "Invalid pyramid level count '{}'",
+                levels
+            ))?),
+            None if args.contains(&"--pyramid".to_string()) => None,
+            None => Some(0),
+        };
+        
+        let hdr = args.contains(&"--hdr".to_string()) || args.contains(&"--16bit".to_string());
+        let quality = match arg_value(args, "--max-error") {
+            Some(max_error) => match max_error.parse::<u32>() {
+                Ok(0) => Quality::Lossless,
+                Ok(max_error) if max_error <= max_sample_error(hdr) => Quality::NearLossless { max_error },
+                _ => return Err(format!(
+                    "Invalid max error '{}' (expected 0 to {})",
+                    max_error, max_sample_error(hdr)
+                )),
+            },
+            None => Quality::Lossless,
+        };
+        
+        let import_exif = !args.contains(&"--no-exif".to_string());
+        let keep_exif = args.contains(&"--keep-exif".to_string());
+        if keep_exif && !import_exif {
+            return Err("--keep-exif cannot be combined with --no-exif".to_string());
+        }
+        
+        // Checked here so a bad value fails the command instead of being ignored
+        source_date_epoch()?;
+        
+        let profile = arg_value(args, "--profile").map(str::to_string);
+        match (color_model, &profile) {
+            (ColorModel::Cmyk, None) => {
+                return Err("--color-model cmyk requires --profile <cmyk.icc>".to_string());
+            }
+            (ColorModel::Lab, Some(_)) => {
+                return Err("--profile cannot be combined with --color-model lab".to_string());
+            }
+            _ => {}
+        }
+        
+        Ok(Self {
+            compression,
+            level,
+            auto,
+            dictionary,
+            use_binary: args.contains(&"--binary".to_string()),
+            hdr,
+            color_model,
+            profile,
+            color: ColorTransformOptions::from_args(args)?,
+            thumbnail,
+            tiles,
+            strip_rows,
+            pyramid_levels,
+            interlace,
+            quality,
+            import_exif,
+            keep_exif,
+            apply_orientation: args.contains(&"--apply-orientation".to_string()),
+            reproducible: args.contains(&"--reproducible".to_string()),
+        })
+    }
+}
+
+fn png_to_yeet_v3(path: PathBuf, options: &CompileOptions) -> Result<(), std::io::Error> {
+    println!("[INFO] v3 format is experimental");
+    
+    let yeet_img = png_to_yeet_image(&path, options)?;
+    let (width, height) = (yeet_img.width, yeet_img.height);
+    
+    let output_path = path.with_extension("yeet");
+    let yeet_img = if options.auto {
+        let (chosen, encoded, candidates) = optimize_encoding(&yeet_img, options.level)?;
+        print_encoding_candidates(&candidates, &chosen);
+        fs::write(&output_path, encoded)?;
+        chosen
+    } else {
+        write_yeet_v3(&output_path, &yeet_img)?;
+        yeet_img
+    };
+    
+    println!("[OK] Converted to YEET v3: {}", output_path.display());
+    println!("  Dimensions: {}x{}", width, height);
+    println!("  Format: v3 (experimental)");
+    println!("  Encoding: {}", describe_encoding(&yeet_img));
+    println!("  File size: {} bytes", fs::metadata(&output_path)?.len());
+    println!("  Bit depth: {}", yeet_img.metadata.bit_depth);
+    println!("  Color model: {}", yeet_img.metadata.color_model.name());
+    println!("  ICC Profile: {}", yeet_img.icc_profile.is_some());
+    if let Some(ref thumbnail) = yeet_img.thumbnail {
+        println!("  Thumbnail: {}x{}", thumbnail.width, thumbnail.height);
+    }
+    if let Some(layout) = yeet_img.metadata.tiles {
+        println!("  Tiles: {}x{} ({} tiles)", layout.width, layout.height,
+                 layout.tile_count(width, height));
+    }
+    if yeet_img.metadata.pyramid_levels > 0 {
+        println!("  Pyramid levels: {}", yeet_img.metadata.pyramid_levels);
+    }
+    if yeet_img.metadata.interlace == Interlace::Adam7 {
+        println!("  Interlaced: Adam7");
+    }
+    if yeet_img.metadata.quality != Quality::Lossless {
+        println!("  Quality: {}", yeet_img.metadata.quality.describe());
+    }
+    if yeet_img.metadata.orientation() != Orientation::Normal {
+        println!("  Orientation: {}", yeet_img.metadata.orientation().name());
+    }
+    if let Some((x_dpi, y_dpi)) = yeet_img.metadata.dpi {
+        let (width, height) = yeet_img.metadata.orientation().display_size(width, height);
+        match physical_size_inches(&yeet_img.metadata, width, height) {
+            Some(size) => println!("  DPI: {}x{} ({})", x_dpi, y_dpi, describe_physical_size(size)),
+            None => println!("  DPI: {}x{}", x_dpi, y_dpi),
+        }
+    }
+    if let Some(ref camera) = yeet_img.metadata.camera {
+        println!("  Camera: {}", camera.summary());
+    }
+    if let Some(ref exif) = yeet_img.metadata.exif {
+        println!("  EXIF: kept ({} bytes)", exif.len() / 2);
+    }
+    if !yeet_img.metadata.text.is_empty() {
+        let keywords: Vec<&str> = yeet_img.metadata.text.keys().map(String::as_str).collect();
+        println!("  Text: {}", keywords.join(", "));
+    }
+    if let Some(ref xmp) = yeet_img.metadata.xmp {
+        println!("  XMP: {} bytes", xmp.len());
+    }
+    
+    Ok(())
+}
+
+/// One encoding tried by `--auto` / `optimize` and the file size it produced
+struct EncodingCandidate {
+    is_binary: bool,
+    compression: CompressionAlgorithm,
+    tiles: Option<TileLayout>,
+    interlace: Interlace,
+    size: usize,
+}
+
+/// Pixel encoding and compression of an image, e.g. "binary + zstd (level 19)"
+fn describe_encoding(yeet_img: &YeetImageV3) -> String {
+    let encoding = if yeet_img.is_binary { "binary" } else { "hex" };
+    match yeet_img.compression {
+        compression if !compression.has_levels() => format!("{} + {}", encoding, compression.name()),
+        compression => format!(
+            "{} + {} (level {})",
+            encoding,
+            compression.name(),
+            yeet_img.compression_level.unwrap_or_else(|| compression.default_level())
+        ),
+    }
+}
+
+/// Frame layout of an image, e.g. "256-row strips" or "single stream"
+fn describe_layout(width: u32, tiles: Option<TileLayout>, interlace: Interlace) -> String {
+    match tiles {
+        Some(layout) if layout.width >= width => format!("{}-row strips", layout.height),
+        Some(layout) => format!("{}x{} tiles", layout.width, layout.height),
+        None if interlace == Interlace::Adam7 => "adam7".to_string(),
+        None => "single stream".to_string(),
+    }
+}
+
+/// `--level` given together with `--auto` (or to `optimize`): it is used for
+/// every codec whose range contains it, so it only has to fit one of them
+fn auto_level_arg(args: &[String]) -> Result<Option<i32>, String> {
+    let Some(level) = arg_value(args, "--level") else {
+        return Ok(None);
+    };
+    match level.parse::<i32>() {
+        Ok(level) if CODECS.iter().any(|codec| codec.algorithm.level_range().contains(&level)) => {
+            Ok(Some(level))
+        }
+        _ => Err(format!("Invalid level '{}' (no codec accepts it)", level)),
+    }
+}
+
+/// Encode the image in hex and binary with every compression algorithm and
+/// frame layout (strips, single stream, Adam7), and keep the smallest file
+///
+/// YEET has no per-row pixel filters like PNG, so the frame layouts stand in
+/// for the "pre-filters" of the search: they change what the codec sees.
+///
+/// `level` applies to every codec that accepts it; the others use their
+/// default level. Images with real tiles keep them, since region decoding
+/// depends on the tile size. Returns the chosen image, its encoded file and
+/// every candidate's size. Candidates are compared in a fixed order, so the
+/// choice does not depend on the thread count.
+fn optimize_encoding(
+    yeet_img: &YeetImageV3,
+    level: Option<i32>,
+) -> Result<(YeetImageV3, Vec<u8>, Vec<EncodingCandidate>), std::io::Error> {
+    let raw_frames: Vec<Vec<u8>> = (0..yeet_img.frames.len())
+        .map(|index| frame_raw_pixels(yeet_img, index))
+        .collect();
+    let hex_frames: Vec<Vec<u8>> = raw_frames.iter().map(|raw| encode_hex_pixels(raw)).collect();
+    
+    let (width, height) = (yeet_img.width, yeet_img.height);
+    let layouts: Vec<(Option<TileLayout>, Interlace)> = match yeet_img.metadata.tiles {
+        Some(layout) if layout.width < width => vec![(Some(layout), Interlace::None)],
+        tiles => {
+            let strips = tiles.unwrap_or(TileLayout { width, height: DEFAULT_STRIP_ROWS.min(height) });
+            vec![
+                (Some(strips), Interlace::None),
+                (None, Interlace::None),
+                (None, Interlace::Adam7),
+            ]
+        }
+    };
+    
+    type Setting = (bool, CompressionAlgorithm, Option<TileLayout>, Interlace);
+    let with_encoding = |&(is_binary, compression, tiles, interlace): &Setting| {
+        let data = if is_binary { &raw_frames } else { &hex_frames };
+        YeetImageV3 {
+            width,
+            height,
+            has_alpha: yeet_img.has_alpha,
+            is_hdr: yeet_img.is_hdr,
+            is_binary,
+            compression,
+            compression_level: level.filter(|level| {
+                compression.has_levels() && compression.level_range().contains(level)
+            }),
+            dictionary: yeet_img.dictionary.clone(),
+            loop_count: yeet_img.loop_count,
+            metadata: YeetMetadataV3 { tiles, interlace, ..yeet_img.metadata.clone() },
+            thumbnail: yeet_img.thumbnail.clone(),
+            icc_profile: yeet_img.icc_profile.clone(),
+            frames: yeet_img.frames.iter().zip(data)
+                .map(|(frame, data)| YeetFrame { delay: frame.delay, data: data.clone() })
+                .collect(),
+        }
+    };
+    
+    let settings: Vec<Setting> = [true, false]
+        .iter()
+        .flat_map(|&is_binary| CODECS.iter().map(move |codec| (is_binary, codec.algorithm)))
+        .flat_map(|(is_binary, compression)| {
+            layouts.iter().map(move |&(tiles, interlace)| (is_binary, compression, tiles, interlace))
+        })
+        .collect();
+    let encoded = settings
+        .par_iter()
+        .map(|setting| {
+            let mut file = Vec::new();
+            write_yeet_v3_to(&mut file, &with_encoding(setting))?;
+            Ok(file)
+        })
+        .collect::<Result<Vec<Vec<u8>>, std::io::Error>>()?;
+    
+    let candidates: Vec<EncodingCandidate> = settings
+        .iter()
+        .zip(&encoded)
+        .map(|(&(is_binary, compression, tiles, interlace), file)| EncodingCandidate {
+            is_binary,
+            compression,
+            tiles,
+            interlace,
+            size: file.len(),
+        })
+        .collect();
+    let best = (0..encoded.len()).min_by_key(|&i| encoded[i].len()).unwrap_or(0);
+    let file = encoded.into_iter().nth(best).unwrap_or_default();
+    
+    Ok((with_encoding(&settings[best]), file, candidates))
+}
+
+/// Print the size of every candidate encoding, marking the chosen one
+fn print_encoding_candidates(candidates: &[EncodingCandidate], chosen: &YeetImageV3) {
+    println!("[INFO] Tried encodings:");
+    for candidate in candidates {
+        let is_chosen = candidate.is_binary == chosen.is_binary
+            && candidate.compression == chosen.compression
+            && candidate.tiles == chosen.metadata.tiles
+            && candidate.interlace == chosen.metadata.interlace;
+        println!(
+            "  {:<6} + {:<6} {:<16} {:>12} bytes{}",
+            if candidate.is_binary { "binary" } else { "hex" },
+            candidate.compression.name(),
+            describe_layout(chosen.width, candidate.tiles, candidate.interlace),
+            candidate.size,
+            if is_chosen { "  <- chosen" } else { "" }
+        );
+    }
+}
+
+/// Load a PNG and build the v3 image described by the compile options
+///
+/// The source ICC profile (embedded, or synthesized from sRGB/gAMA/cHRM) is
+/// carried over unless the options convert to another profile.
+fn png_to_yeet_image(path: &Path, options: &CompileOptions) -> Result<YeetImageV3, std::io::Error> {
+    let img = image::open(path).map_err(std::io::Error::other)?;
+    let mut width = img.width();
+    let mut height = img.height();
+    
+    let has_alpha = img.color().has_alpha();
+    let is_hdr = options.hdr;
+    
+    // Extract ICC profile (or synthesize one from sRGB/gAMA/cHRM)
+    let (mut icc_profile, mut png_color_space) = match extract_icc_profile(path) {
+        Some((profile, color_space)) => (Some(profile), color_space),
+        None => (None, None),
+    };
+    
+    // Encode pixel data (16-bit samples are stored big-endian)
+    let raw_pixels = |samples: Vec<u16>| -> Vec<u8> {
+        samples.iter().flat_map(|s| s.to_be_bytes()).collect()
+    };
+    let mut raw: Vec<u8> = match (has_alpha, is_hdr) {
+        (true, false) => img.to_rgba8().into_raw(),
+        (false, false) => img.to_rgb8().into_raw(),
+        (true, true) => raw_pixels(img.to_rgba16().into_raw()),
+        (false, true) => raw_pixels(img.to_rgb16().into_raw()),
+    };
+    
+    // Convert to the requested profile / color model (untagged sources are treated as sRGB)
+    let color_model = options.color_model;
+    let target = match options.profile {
+        Some(ref profile_arg) => Some(load_profile_arg(profile_arg)?),
+        None if color_model == ColorModel::Lab => Some(lab_profile_icc()?),
+        None => None,
+    };
+    if let Some(target) = target {
+        check_profile_color_model(&target, color_model)?;
+        let source = match icc_profile {
+            Some(ref p) => p.clone(),
+            None => load_profile_arg("sRGB")?,
+        };
+        raw = convert_icc_pixels(
+            &raw,
+            &source,
+            icc_pixel_format(ColorModel::Rgb, has_alpha, is_hdr),
+            &target,
+            icc_pixel_format(color_model, has_alpha, is_hdr),
+            options.color,
+        )?;
+        // Lab is device independent, so it is stored untagged
+        icc_profile = (color_model != ColorModel::Lab).then_some(target);
+        png_color_space = match color_model {
+            ColorModel::Rgb => None,
+            _ => Some(color_model.name().to_string()),
+        };
+    }
+    
+    // EXIF camera data, DPI and orientation; --apply-orientation turns the pixels upright
+    let exif = options.import_exif.then(|| read_source_exif(path)).flatten();
+    let orientation = exif.as_ref().and_then(exif_orientation).unwrap_or_default();
+    let bake_orientation = options.apply_orientation && orientation != Orientation::Normal;
+    if bake_orientation {
+        let bytes_per_pixel = (color_model.channels() + usize::from(has_alpha)) * if is_hdr { 2 } else { 1 };
+        raw = orientation.apply(&raw, width, height, bytes_per_pixel);
+        (width, height) = orientation.display_size(width, height);
+    }
+    
+    if let Quality::NearLossless { max_error } = options.quality {
+        quantize_near_lossless(&mut raw, width as usize, color_model.channels(), has_alpha, is_hdr, max_error);
+    }
+    
+    // Prepare metadata
+    let mut metadata = YeetMetadataV3 {
+        frame_count: 1,
+        color_profile: icc_profile.as_deref().map(|p| {
+            icc_description(p).unwrap_or_else(|| "embedded".to_string())
+        }),
+        color_model,
+        bit_depth: if is_hdr { 16 } else { 8 },
+        hdr: is_hdr,
+        tiles: options.tiles.or_else(|| {
+            options.strip_rows.map(|rows| TileLayout { width, height: rows.min(height) })
+        }),
+        pyramid_levels: options.pyramid_levels.unwrap_or_else(|| auto_pyramid_levels(width, height)),
+        interlace: options.interlace,
+        quality: options.quality,
+        ..Default::default()
+    };
+    // Without SOURCE_DATE_EPOCH there is no fixed time, so --reproducible leaves `created` out
+    if options.reproducible && !matches!(source_date_epoch(), Ok(Some(_))) {
+        metadata.created = None;
+    }
+    if png_color_space.is_some() {
+        metadata.color_space = png_color_space;
+    }
+    if let Some(ref exif) = exif {
+        apply_exif(&mut metadata, exif);
+        if options.keep_exif {
+            metadata.exif = Some(hex_string(&raw_exif(path, exif)?));
+        }
+    }
+    if let Some(dpi) = source_dpi(path) {
+        metadata.dpi = Some(dpi);
+    }
+    // XMP travels in its own metadata field
+    metadata.text = source_text_chunks(path);
+    metadata.text.remove(XMP_PNG_KEYWORD);
+    metadata.xmp = source_xmp(path);
+    if bake_orientation {
+        metadata.orientation = Some(Orientation::Normal);
+        if orientation.swaps_axes() {
+            metadata.dpi = metadata.dpi.map(|(x, y)| (y, x));
+        }
+    }
+    
+    let pixel_data = if options.use_binary { raw } else { encode_hex_pixels(&raw) };
+    
+    let mut yeet_img = YeetImageV3 {
+        width,
+        height,
+        has_alpha,
+        is_hdr,
+        is_binary: options.use_binary,
+        compression: options.compression,
+        compression_level: options.level,
+        dictionary: options.dictionary.clone(),
+        loop_count: 0,
+        metadata,
+        thumbnail: None,
+        icc_profile,
+        frames: vec![YeetFrame { delay: 0, data: pixel_data }],
+    };
+    if let Some(max_edge) = options.thumbnail {
+        yeet_img.thumbnail = Some(make_thumbnail(&yeet_img, max_edge));
+    }
+    
+    Ok(yeet_img)
+}
+
+// ============================================================================
+// EXIF Import
+// ============================================================================
+
+/// Descriptive IFD0 tags kept when a TIFF source's EXIF is rebuilt without its pixels
+const TIFF_EXIF_TAGS: &[exif::Tag] = &[
+    exif::Tag::ImageDescription,
+    exif::Tag::Make,
+    exif::Tag::Model,
+    exif::Tag::Orientation,
+    exif::Tag::XResolution,
+    exif::Tag::YResolution,
+    exif::Tag::ResolutionUnit,
+    exif::Tag::Software,
+    exif::Tag::DateTime,
+    exif::Tag::Artist,
+    exif::Tag::Copyright,
+];
+
+/// EXIF of a JPEG, TIFF, PNG (`eXIf`), HEIF or WebP source, if it has any
+fn read_source_exif(path: &Path) -> Option<exif::Exif> {
+    let file = File::open(path).ok()?;
+    match exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)) {
+        Ok(exif) => Some(exif),
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::InvalidFormat("Unknown image format")) => None,
+        Err(e) => {
+            eprintln!("[WARN] Ignoring unreadable EXIF in {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Fill `camera`, `dpi` and `orientation` from the primary image's EXIF tags
+fn apply_exif(metadata: &mut YeetMetadataV3, exif: &exif::Exif) {
+    let field = |tag| exif.get_field(tag, exif::In::PRIMARY);
+    let text = |tag| match field(tag).map(|f| &f.value) {
+        Some(exif::Value::Ascii(values)) => values.first()
+            .map(|v| String::from_utf8_lossy(v).trim_end_matches('\0').trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    };
+    let display = |tag| field(tag).map(|f| f.display_value().to_string());
+    
+    let camera = CameraMetadata {
+        make: text(exif::Tag::Make),
+        model: text(exif::Tag::Model),
+        iso: field(exif::Tag::PhotographicSensitivity).and_then(|f| f.value.get_uint(0)),
+        exposure: display(exif::Tag::ExposureTime),
+        aperture: display(exif::Tag::FNumber).map(|f| format!("f/{}", f)),
+        focal_length: display(exif::Tag::FocalLength).map(|f| format!("{} mm", f)),
+    };
+    if camera.make.is_some() || camera.model.is_some() || camera.iso.is_some()
+        || camera.exposure.is_some() || camera.aperture.is_some() || camera.focal_length.is_some()
+    {
+        metadata.camera = Some(camera);
+    }
+    
+    // Resolution unit 2 is inches (the default), 3 centimetres; 1 means unknown
+    let resolution = |tag| match field(tag).map(|f| &f.value) {
+        Some(exif::Value::Rational(values)) => values.first()
+            .filter(|r| r.denom != 0)
+            .map(|r| r.to_f64()),
+        _ => None,
+    };
+    let per_inch = match field(exif::Tag::ResolutionUnit).and_then(|f| f.value.get_uint(0)) {
+        None | Some(2) => Some(1.0),
+        Some(3) => Some(2.54),
+        _ => None,
+    };
+    if let (Some(x), Some(y), Some(scale)) = (resolution(exif::Tag::XResolution), resolution(exif::Tag::YResolution), per_inch) {
+        metadata.dpi = Some(((x * scale).round() as u32, (y * scale).round() as u32));
+    }
+    
+    if let Some(orientation) = exif_orientation(exif) {
+        metadata.orientation = Some(orientation);
+    }
+}
+
+fn exif_orientation(exif: &exif::Exif) -> Option<Orientation> {
+    let value = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)?;
+    let orientation = Orientation::from_exif(value);
+    if orientation.is_none() {
+        eprintln!("[WARN] Ignoring invalid EXIF orientation {}", value);
+    }
+    orientation
+}
+
+/// The raw EXIF block (TIFF structure) to keep with `--keep-exif`
+///
+/// JPEG, PNG, HEIF and WebP carry a standalone block. For TIFF sources the block
+/// is the whole file, so it is rebuilt from the EXIF, GPS and descriptive tags
+/// without the pixel data.
+fn raw_exif(path: &Path, exif: &exif::Exif) -> Result<Vec<u8>, std::io::Error> {
+    let mut magic = [0u8; 4];
+    File::open(path)?.read_exact(&mut magic)?;
+    if &magic != b"II*\0" && &magic != b"MM\0*" {
+        return Ok(exif.buf().to_vec());
+    }
+    
+    let mut writer = exif::experimental::Writer::new();
+    for field in exif.fields().filter(|f| f.ifd_num == exif::In::PRIMARY) {
+        if field.tag.context() != exif::Context::Tiff || TIFF_EXIF_TAGS.contains(&field.tag) {
+            writer.push_field(field);
+        }
+    }
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    writer.write(&mut buffer, exif.little_endian()).map_err(std::io::Error::other)?;
+    Ok(buffer.into_inner())
+}
+
+// ============================================================================
+// Resolution (DPI)
+// ============================================================================
+
+/// DPI from a PNG `pHYs` chunk or a JPEG JFIF header (TIFF resolution comes via EXIF)
+///
+/// Densities without a unit only give the pixel aspect ratio and are ignored.
+fn source_dpi(path: &Path) -> Option<(u32, u32)> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.starts_with(b"\x89PNG") {
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let reader = decoder.read_info().ok()?;
+        let dims = reader.info().pixel_dims?;
+        let dpi = |ppm: u32| (ppm as f64 * METERS_PER_INCH).round() as u32;
+        return (dims.unit == png::Unit::Meter).then(|| (dpi(dims.xppu), dpi(dims.yppu)));
+    }
+    
+    // JFIF APP0: "JFIF\0", version (2), units (1 = dpi, 2 = dots per cm), X and Y density
+    let app0 = bytes.strip_prefix(b"\xFF\xD8\xFF\xE0")?;
+    let jfif = app0.get(2..14).filter(|segment| segment.starts_with(b"JFIF\0"))?;
+    let density = |offset: usize| u16::from_be_bytes([jfif[offset], jfif[offset + 1]]) as f64;
+    let per_inch = match jfif[7] {
+        1 => 1.0,
+        2 => 2.54,
+        _ => return None,
+    };
+    Some(((density(8) * per_inch).round() as u32, (density(10) * per_inch).round() as u32))
+}
+
+/// Printed size in inches of a `width`×`height` display image
+fn physical_size_inches(metadata: &YeetMetadataV3, width: u32, height: u32) -> Option<(f64, f64)> {
+    let (x_dpi, y_dpi) = metadata.dpi.filter(|&(x, y)| x > 0 && y > 0)?;
+    let (x_dpi, y_dpi) = if metadata.orientation().swaps_axes() { (y_dpi, x_dpi) } else { (x_dpi, y_dpi) };
+    Some((width as f64 / x_dpi as f64, height as f64 / y_dpi as f64))
+}
+
+/// "8.00 × 5.33 in (20.32 × 13.55 cm)"
+fn describe_physical_size((width, height): (f64, f64)) -> String {
+    format!("{:.2} × {:.2} in ({:.2} × {:.2} cm)", width, height, width * 2.54, height * 2.54)
+}
+
+// ============================================================================
+// XMP
+// ============================================================================
+
+/// Namespace header of a JPEG APP1 segment holding XMP
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// XMP packet of a PNG (`iTXt XML:com.adobe.xmp`) or JPEG (APP1) source
+fn source_xmp(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.starts_with(b"\x89PNG") {
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().ok()?;
+        reader.finish().ok()?;
+        let chunk = reader.info().utf8_text.iter().find(|chunk| chunk.keyword == XMP_PNG_KEYWORD)?;
+        return chunk.get_text().ok();
+    }
+    
+    // Walk the JPEG segments up to the start of scan
+    let mut segments = bytes.strip_prefix(b"\xFF\xD8")?;
+    while let [0xFF, marker, high, low, rest @ ..] = segments {
+        let len = u16::from_be_bytes([*high, *low]) as usize;
+        let payload = rest.get(..len.checked_sub(2)?)?;
+        if let (0xE1, Some(packet)) = (*marker, payload.strip_prefix(XMP_JPEG_HEADER)) {
+            return Some(String::from_utf8_lossy(packet).into_owned());
+        }
+        if *marker == 0xDA {
+            break;
+        }
+        segments = &rest[payload.len()..];
+    }
+    None
+}
+
+/// Check that `packet` is XMP before it is embedded
+fn check_xmp_packet(packet: &str) -> Result<(), String> {
+    if packet.contains("<x:xmpmeta") || packet.contains("<?xpacket") {
+        Ok(())
+    } else {
+        Err("not an XMP packet (no <x:xmpmeta> or <?xpacket> element)".to_string())
+    }
+}
+
+// ============================================================================
+// Near-Lossless Encoding
+// ============================================================================
+
+/// Largest `--max-error` (half the sample range; anything more is a flat image)
+fn max_sample_error(is_hdr: bool) -> u32 {
+    if is_hdr { 32767 } else { 127 }
+}
+
+/// Move every color sample by at most `max_error` so rows compress better
+///
+/// Each sample is predicted from its reconstructed left neighbour (the one above
+/// at the start of a row). Residuals within `max_error` collapse onto the
+/// prediction, producing runs; larger ones are rounded to a multiple of
+/// `2 * max_error + 1` steps away from it. Alpha is left untouched.
+fn quantize_near_lossless(
+    raw: &mut [u8],
+    width: usize,
+    channels: usize,
+    has_alpha: bool,
+    is_hdr: bool,
+    max_error: u32,
+) {
+    let bytes_per_sample = if is_hdr { 2 } else { 1 };
+    let samples_per_pixel = channels + has_alpha as usize;
+    let row_samples = width * samples_per_pixel;
+    let max_value: i64 = if is_hdr { 65535 } else { 255 };
+    let error = max_error as i64;
+    let step = 2 * error + 1;
+    
+    let get = |raw: &[u8], index: usize| -> i64 {
+        let offset = index * bytes_per_sample;
+        if is_hdr {
+            u16::from_be_bytes([raw[offset], raw[offset + 1]]) as i64
+        } else {
+            raw[offset] as i64
+        }
+    };
+    let set = |raw: &mut [u8], index: usize, value: i64| {
+        let offset = index * bytes_per_sample;
+        if is_hdr {
+            raw[offset..offset + 2].copy_from_slice(&(value as u16).to_be_bytes());
+        } else {
+            raw[offset] = value as u8;
+        }
+    };
+    
+    let sample_count = raw.len() / bytes_per_sample;
+    for index in 0..sample_count {
+        let position = index % row_samples;
+        if position % samples_per_pixel >= channels {
+            continue;
+        }
+        let prediction = if position >= samples_per_pixel {
+            get(raw, index - samples_per_pixel)
+        } else if index >= row_samples {
+            get(raw, index - row_samples)
+        } else {
+            continue; // First pixel is kept exact
+        };
+        
+        // Round the residual to the nearest multiple of `step`; clamping only
+        // moves the value back towards the source, so the bound still holds
+        let residual = get(raw, index) - prediction;
+        let steps = if residual >= 0 {
+            (residual + error) / step
+        } else {
+            -((error - residual) / step)
+        };
+        set(raw, index, (prediction + steps * step).clamp(0, max_value));
+    }
+}
+
+/// Longest edge `--pyramid` reduces the smallest level to
+const PYRAMID_MIN_EDGE: u32 = 512;
+
+/// Number of halvings until the longest edge fits `PYRAMID_MIN_EDGE`
+fn auto_pyramid_levels(width: u32, height: u32) -> u32 {
+    let mut edge = width.max(height);
+    let mut levels = 0;
+    while edge > PYRAMID_MIN_EDGE {
+        edge = edge.div_ceil(2);
+        levels += 1;
+    }
+    levels
+}
+
+/// Default longest edge of embedded thumbnails
+const THUMBNAIL_MAX_EDGE: u32 = 256;
+
+/// Render the first frame for display (sRGB) and shrink it to fit `max_edge`
+fn make_thumbnail(yeet_img: &YeetImageV3, max_edge: u32) -> YeetThumbnail {
+    let settings = DisplaySettings {
+        color: ColorTransformOptions::default(),
+        proofing: None,
+    };
+    let transform = DisplayTransform::new(yeet_img, &settings);
+    let full = yeet_v3_to_image(yeet_img, 0, transform.as_ref());
+    
+    let longest = yeet_img.width.max(yeet_img.height).max(1);
+    let scale = |edge: u32| ((edge as u64 * max_edge as u64) / longest as u64).max(1) as u32;
+    let (width, height) = if longest <= max_edge {
+        (yeet_img.width, yeet_img.height)
+    } else {
+        (scale(yeet_img.width), scale(yeet_img.height))
+    };
+    
+    let thumbnail = image::imageops::thumbnail(&full, width, height);
+    YeetThumbnail {
+        width,
+        height,
+        data: thumbnail.into_raw(),
+    }
+}
+
+/// Write a v3 file, compressing every frame with the image's compression algorithm
+fn write_yeet_v3(output_path: &Path, yeet_img: &YeetImageV3) -> Result<(), std::io::Error> {
+    let mut file = File::create(output_path)?;
+    write_yeet_v3_to(&mut file, yeet_img)
+}
+
+/// Encode a v3 file into any writer (used to size candidate encodings in memory)
+fn write_yeet_v3_to(file: &mut impl Write, yeet_img: &YeetImageV3) -> Result<(), std::io::Error> {
+    // Codecs without flag bits are named in the metadata instead
+    let codec = yeet_img.compression.info();
+    let mut metadata = yeet_img.metadata.clone();
+    metadata.codec = codec.flag_bits.is_none().then(|| codec.name.to_string());
+    let dictionary = yeet_img.dictionary.as_ref().filter(|_| yeet_img.compression == CompressionAlgorithm::Zstd);
+    metadata.zstd_dictionary = dictionary.map(|d| DictionaryRef { id: d.id, embedded: d.embed });
+    let metadata_json = serde_json::to_string(&metadata).unwrap();
+    let metadata_bytes = metadata_json.as_bytes();
+    
+    // Build flags
+    let mut flags: u8 = codec.flag_bits.unwrap_or(0) & 0b00000011;
+    if yeet_img.has_alpha { flags |= 0b00000100; }
+    if yeet_img.is_binary { flags |= 0b00001000; }
+    if yeet_img.frames.len() > 1 { flags |= 0b00010000; }
+    if yeet_img.icc_profile.is_some() { flags |= 0b00100000; }
+    if yeet_img.is_hdr { flags |= 0b01000000; }
+    if yeet_img.thumbnail.is_some() { flags |= 0b10000000; }
+    
+    // Header
+    file.write_all(b"YEET")?;
+    file.write_all(&[3])?; // Version 3
+    file.write_all(&[flags])?;
+    file.write_all(&yeet_img.width.to_le_bytes())?;
+    file.write_all(&yeet_img.height.to_le_bytes())?;
+    file.write_all(&(yeet_img.frames.len() as u32).to_le_bytes())?;
+    file.write_all(&yeet_img.loop_count.to_le_bytes())?;
+    
+    // Metadata
+    file.write_all(&(metadata_bytes.len() as u32).to_le_bytes())?;
+    file.write_all(metadata_bytes)?;
+    
+    // Embedded zstd dictionary
+    if let Some(dictionary) = dictionary.filter(|d| d.embed) {
+        file.write_all(&(dictionary.data.len() as u32).to_le_bytes())?;
+        file.write_all(&dictionary.data)?;
+    }
+    
+    // Thumbnail
+    if let Some(ref thumbnail) = yeet_img.thumbnail {
+        let compressed_data = yeet_img.compress(&thumbnail.data);
+        file.write_all(&thumbnail.width.to_le_bytes())?;
+        file.write_all(&thumbnail.height.to_le_bytes())?;
+        file.write_all(&(compressed_data.len() as u32).to_le_bytes())?;
+        file.write_all(&compressed_data)?;
+    }
+    
+    // ICC profile
+    if let Some(ref profile) = yeet_img.icc_profile {
+        file.write_all(&(profile.len() as u32).to_le_bytes())?;
+        file.write_all(profile)?;
+    } else {
+        file.write_all(&0u32.to_le_bytes())?;
+    }
+    
+    // Frame data (frames compress in parallel, then are written in order)
+    let payloads: Vec<Vec<u8>> = (0..yeet_img.frames.len())
+        .into_par_iter()
+        .map(|index| match yeet_img.metadata.tiles {
+            Some(layout) => encode_tiled_pixels(
+                yeet_img,
+                &frame_raw_pixels(yeet_img, index),
+                (yeet_img.width, yeet_img.height),
+                layout,
+            ),
+            None if yeet_img.metadata.interlace == Interlace::Adam7 => {
+                let raw = frame_raw_pixels(yeet_img, index);
+                let interlaced = interlace_adam7(&raw, yeet_img.width, yeet_img.height, yeet_img.bytes_per_pixel());
+                let data = if yeet_img.is_binary { interlaced } else { encode_hex_pixels(&interlaced) };
+                yeet_img.compress(&data)
+            }
+            None => yeet_img.compress(&yeet_img.frames[index].data),
+        })
+        .collect();
+    for (frame, compressed_data) in yeet_img.frames.iter().zip(&payloads) {
+        file.write_all(&frame.delay.to_le_bytes())?;
+        file.write_all(&(compressed_data.len() as u32).to_le_bytes())?;
+        file.write_all(compressed_data)?;
+    }
+    
+    // Pyramid levels (always rebuilt from the frames). Each frame builds its
+    // own chain of levels in parallel; the file stores them level by level.
+    if yeet_img.metadata.pyramid_levels > 0 {
+        let chains: Vec<Vec<Vec<u8>>> = (0..yeet_img.frames.len())
+            .into_par_iter()
+            .map(|index| {
+                let mut raw = frame_raw_pixels(yeet_img, index);
+                let mut size = (yeet_img.width, yeet_img.height);
+                (0..yeet_img.metadata.pyramid_levels)
+                    .map(|_| {
+                        (raw, size) = downsample_half(yeet_img, &raw, size);
+                        match yeet_img.metadata.tiles {
+                            Some(layout) => encode_tiled_pixels(yeet_img, &raw, size, layout),
+                            None if yeet_img.is_binary => yeet_img.compress(&raw),
+                            None => yeet_img.compress(&encode_hex_pixels(&raw)),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        
+        for level in 0..yeet_img.metadata.pyramid_levels as usize {
+            for chain in &chains {
+                file.write_all(&(chain[level].len() as u32).to_le_bytes())?;
+                file.write_all(&chain[level])?;
+            }
+        }
+    }
+    
+    file.flush()
+}
+
+// ============================================================================
+// Tiled Storage
+// ============================================================================
+
+/// Rows per strip, the default layout of still images (strips are full-width tiles)
+const DEFAULT_STRIP_ROWS: u32 = 256;
+
+/// Fixed tile size of a tiled image (edge tiles are clipped to the image)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct TileLayout {
+    width: u32,
+    height: u32,
+}
+
+impl TileLayout {
+    /// Parse `256` (square) or `512x256`
+    fn parse(value: &str) -> Option<Self> {
+        let (width, height) = match value.split_once(['x', 'X']) {
+            Some((w, h)) => (w.parse().ok()?, h.parse().ok()?),
+            None => {
+                let size = value.parse().ok()?;
+                (size, size)
+            }
+        };
+        (width > 0 && height > 0).then_some(Self { width, height })
+    }
+    
+    fn columns(self, image_width: u32) -> u32 {
+        image_width.div_ceil(self.width)
+    }
+    
+    fn rows(self, image_height: u32) -> u32 {
+        image_height.div_ceil(self.height)
+    }
+    
+    fn tile_count(self, image_width: u32, image_height: u32) -> usize {
+        self.columns(image_width) as usize * self.rows(image_height) as usize
+    }
+    
+    /// Pixel rectangle `(x, y, width, height)` covered by a tile
+    fn tile_rect(self, image_width: u32, image_height: u32, index: usize) -> (u32, u32, u32, u32) {
+        let columns = self.columns(image_width) as usize;
+        let x = (index % columns) as u32 * self.width;
+        let y = (index / columns) as u32 * self.height;
+        (x, y, self.width.min(image_width - x), self.height.min(image_height - y))
+    }
+}
+
+/// Copy a `width`×`height` block of pixels between two rasters
+///
+/// Rasters are `(data, stride)` pairs; positions and strides are in pixels,
+/// `bpp` is bytes per pixel.
+fn copy_pixel_rect(
+    (src, src_stride): (&[u8], usize), src_pos: (usize, usize),
+    (dst, dst_stride): (&mut [u8], usize), dst_pos: (usize, usize),
+    (width, height): (usize, usize),
+    bpp: usize,
+) {
+    for row in 0..height {
+        let src_start = ((src_pos.1 + row) * src_stride + src_pos.0) * bpp;
+        let dst_start = ((dst_pos.1 + row) * dst_stride + dst_pos.0) * bpp;
+        let (Some(src_row), Some(dst_row)) = (
+            src.get(src_start..src_start + width * bpp),
+            dst.get_mut(dst_start..dst_start + width * bpp),
+        ) else {
+            return;
+        };
+        dst_row.copy_from_slice(src_row);
+    }
+}
+
+/// Payload of a tiled frame (or pyramid level): tile count, tile length table, compressed tiles
+fn encode_tiled_pixels(
+    yeet_img: &YeetImageV3,
+    raw: &[u8],
+    (width, height): (u32, u32),
+    layout: TileLayout,
+) -> Vec<u8> {
+    let bpp = yeet_img.bytes_per_pixel();
+    let tile_count = layout.tile_count(width, height);
+    
+    // Tiles compress independently; the ordered collect keeps the output
+    // identical for any thread count
+    let tiles: Vec<Vec<u8>> = (0..tile_count)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y, w, h) = layout.tile_rect(width, height, index);
+            let mut tile = vec![0u8; w as usize * h as usize * bpp];
+            copy_pixel_rect(
+                (raw, width as usize), (x as usize, y as usize),
+                (&mut tile, w as usize), (0, 0),
+                (w as usize, h as usize),
+                bpp,
+            );
+            let tile = if yeet_img.is_binary { tile } else { encode_hex_pixels(&tile) };
+            yeet_img.compress(&tile)
+        })
+        .collect();
+    
+    let mut payload = Vec::with_capacity(4 + tile_count * 4 + tiles.iter().map(Vec::len).sum::<usize>());
+    payload.extend_from_slice(&(tile_count as u32).to_le_bytes());
+    for tile in &tiles {
+        payload.extend_from_slice(&(tile.len() as u32).to_le_bytes());
+    }
+    for tile in &tiles {
+        payload.extend_from_slice(tile);
+    }
+    payload
+}
+
+/// Parse a tiled frame's tile count and length table into `(offset, length)` pairs
+///
+/// Offsets are relative to the end of the table; the table length is returned too.
+fn read_tile_table(
+    reader: &mut impl Read,
+    expected_tiles: usize,
+) -> Result<(Vec<(u64, u32)>, u64), std::io::Error> {
+    let tile_count = read_u32(reader)? as usize;
+    if tile_count != expected_tiles {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Expected {} tiles, found {}", expected_tiles, tile_count)
+        ));
+    }
+    
+    let mut tiles = Vec::with_capacity(tile_count);
+    let mut offset = 0u64;
+    for _ in 0..tile_count {
+        let len = read_u32(reader)?;
+        tiles.push((offset, len));
+        offset += len as u64;
+    }
+    Ok((tiles, 4 + tile_count as u64 * 4))
+}
+
+/// Decompress one tile into raw (binary) pixel bytes
+fn decode_tile(
+    data: &[u8],
+    is_binary: bool,
+    compression: CompressionAlgorithm,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<Vec<u8>, std::io::Error> {
+    let data = decompress_data(data, compression, dictionary)?;
+    Ok(if is_binary { data } else { decode_hex_pixels(&data) })
+}
+
+/// Reassemble a tiled frame payload into the in-memory frame data
+fn decode_tiled_frame(header: &YeetHeaderV3, payload: &[u8], layout: TileLayout) -> Result<Vec<u8>, std::io::Error> {
+    let expected_tiles = layout.tile_count(header.width, header.height);
+    let (tiles, table_len) = read_tile_table(&mut &payload[..], expected_tiles)?;
+    
+    let decoded = tiles
+        .into_par_iter()
+        .map(|(offset, len)| {
+            let start = (table_len + offset) as usize;
+            match payload.get(start..start + len as usize) {
+                Some(data) => decode_tile(data, header.is_binary, header.compression, header.dictionary.as_ref()),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Truncated tile data"
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    let bpp = header.bytes_per_pixel();
+    let mut raw = vec![0u8; header.width as usize * header.height as usize * bpp];
+    for (index, tile) in decoded.into_iter().enumerate() {
+        let (x, y, w, h) = layout.tile_rect(header.width, header.height, index);
+        copy_pixel_rect(
+            (&tile, w as usize), (0, 0),
+            (&mut raw, header.width as usize), (x as usize, y as usize),
+            (w as usize, h as usize),
+            bpp,
+        );
+    }
+    
+    Ok(if header.is_binary { raw } else { encode_hex_pixels(&raw) })
+}
+
+/// Halve raw pixels with a 2×2 box filter (odd edges average what is there)
+///
+/// Works on samples, so it is the same for every color model and bit depth.
+fn downsample_half(yeet_img: &YeetImageV3, raw: &[u8], (width, height): (u32, u32)) -> (Vec<u8>, (u32, u32)) {
+    let bytes_per_sample = if yeet_img.is_hdr { 2 } else { 1 };
+    let channels = yeet_img.bytes_per_pixel() / bytes_per_sample;
+    let (width, height) = (width as usize, height as usize);
+    let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+    
+    let sample = |x: usize, y: usize, c: usize| -> u32 {
+        let idx = ((y * width + x) * channels + c) * bytes_per_sample;
+        match bytes_per_sample {
+            2 => u16::from_be_bytes([raw[idx], raw[idx + 1]]) as u32,
+            _ => raw[idx] as u32,
+        }
+    };
+    
+    let mut out = Vec::with_capacity(half_width * half_height * channels * bytes_per_sample);
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let xs = [2 * x, (2 * x + 1).min(width - 1)];
+            let ys = [2 * y, (2 * y + 1).min(height - 1)];
+            for c in 0..channels {
+                let sum: u32 = ys.iter()
+                    .flat_map(|&sy| xs.iter().map(move |&sx| (sx, sy)))
+                    .map(|(sx, sy)| sample(sx, sy, c))
+                    .sum();
+                let average = (sum + 2) / 4;
+                match bytes_per_sample {
+                    2 => out.extend_from_slice(&(average as u16).to_be_bytes()),
+                    _ => out.push(average as u8),
+                }
+            }
+        }
+    }
+    (out, (half_width as u32, half_height as u32))
+}
+
+/// Random access to the tiles of a tiled file without loading whole frames
+///
+/// Keeps the file open and only remembers where each tile is; decoded tiles
+/// are cached so panning over the same area doesn't hit the disk again.
+/// Untiled frames and pyramid levels are read as a single tile.
+struct TiledReader {
+    file: File,
+    bytes_per_pixel: usize,
+    is_binary: bool,
+    compression: CompressionAlgorithm,
+    dictionary: Option<ZstdDictionary>,
+    interlace: Interlace,
+    // Level 0 is the full image, then the pyramid levels
+    levels: Vec<TiledLevel>,
+    cache: std::collections::HashMap<(usize, usize, usize), Vec<u8>>,
+}
+
+/// Size, tile grid and tile locations of one resolution level
+struct TiledLevel {
+    width: u32,
+    height: u32,
+    layout: TileLayout,
+    // Per frame: absolute file offset and compressed length of every tile
+    frames: Vec<Vec<(u64, u32)>>,
+}
+
+/// Decoded tiles kept around by `TiledReader`
+const TILE_CACHE_SIZE: usize = 256;
+
+impl TiledReader {
+    /// Raw (binary) pixel bytes of a region of a frame, clipped to the image
+    ///
+    /// Only the tiles overlapping the region are read and decompressed.
+    fn decode_region(
+        &mut self,
+        frame_index: usize,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        self.decode_level_region(0, frame_index, x, y, width, height)
+    }
+    
+    /// Like `decode_region`, in the coordinates of a pyramid level
+    fn decode_level_region(
+        &mut self,
+        level: usize,
+        frame_index: usize,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let TiledLevel { width: level_width, height: level_height, layout, .. } = self.levels[level];
+        let x = x.min(level_width);
+        let y = y.min(level_height);
+        let width = width.min(level_width - x);
+        let height = height.min(level_height - y);
+        let bpp = self.bytes_per_pixel;
+        let mut region = vec![0u8; width as usize * height as usize * bpp];
+        if width == 0 || height == 0 {
+            return Ok(region);
+        }
+        
+        let columns = layout.columns(level_width);
+        for row in y / layout.height..=(y + height - 1) / layout.height {
+            for column in x / layout.width..=(x + width - 1) / layout.width {
+                let index = (row * columns + column) as usize;
+                let (tile_x, tile_y, tile_w, tile_h) = layout.tile_rect(level_width, level_height, index);
+                
+                // Overlap of the tile and the region, in level coordinates
+                let left = tile_x.max(x);
+                let top = tile_y.max(y);
+                let right = (tile_x + tile_w).min(x + width);
+                let bottom = (tile_y + tile_h).min(y + height);
+                
+                let tile = self.tile(level, frame_index, index)?;
+                copy_pixel_rect(
+                    (tile, tile_w as usize), ((left - tile_x) as usize, (top - tile_y) as usize),
+                    (&mut region, width as usize), ((left - x) as usize, (top - y) as usize),
+                    ((right - left) as usize, (bottom - top) as usize),
+                    bpp,
+                );
+            }
+        }
+        Ok(region)
+    }
+    
+    /// Number of levels, counting the full-resolution image
+    fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+    
+    /// Width and height of a level
+    fn level_size(&self, level: usize) -> (u32, u32) {
+        (self.levels[level].width, self.levels[level].height)
+    }
+    
+    /// Decoded tile, from the cache or the file
+    fn tile(&mut self, level: usize, frame_index: usize, index: usize) -> Result<&[u8], std::io::Error> {
+        let key = (level, frame_index, index);
+        if !self.cache.contains_key(&key) {
+            let (offset, len) = self.levels[level].frames[frame_index][index];
+            self.file.seek(SeekFrom::Start(offset))?;
+            let data = read_bytes(&mut self.file, len as usize)?;
+            
+            if self.cache.len() >= TILE_CACHE_SIZE {
+                self.cache.clear();
+            }
+            let mut tile = decode_tile(&data, self.is_binary, self.compression, self.dictionary.as_ref())?;
+            if level == 0 && self.interlace == Interlace::Adam7 {
+                let TiledLevel { width, height, .. } = self.levels[0];
+                tile = deinterlace_adam7(&tile, width, height, self.bytes_per_pixel);
+            }
+            self.cache.insert(key, tile);
+        }
+        Ok(&self.cache[&key])
+    }
+}
+
+// ============================================================================
+// Progressive (Interlaced) Encoding
+// ============================================================================
+
+/// Pixel order of untiled frame data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Interlace {
+    #[default]
+    None,
+    Adam7,
+}
+
+/// Adam7 passes as (x offset, y offset, x step, y step), same as PNG
+const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Block each pixel of a pass stands for in a partial preview (width, height)
+const ADAM7_BLOCKS: [(u32, u32); 7] = [(8, 8), (4, 8), (4, 4), (2, 4), (2, 2), (1, 2), (1, 1)];
+
+/// Pixel coordinates in Adam7 storage order, tagged with their pass
+fn adam7_order(width: u32, height: u32) -> impl Iterator<Item = (usize, u32, u32)> {
+    ADAM7_PASSES.iter().enumerate().flat_map(move |(pass, &(x0, y0, dx, dy))| {
+        (y0..height).step_by(dy as usize).flat_map(move |y| {
+            (x0..width).step_by(dx as usize).map(move |x| (pass, x, y))
+        })
+    })
+}
+
+/// Reorder raw pixels from row order into Adam7 pass order
+fn interlace_adam7(raw: &[u8], width: u32, height: u32, bpp: usize) -> Vec<u8> {
+    let mut interlaced = Vec::with_capacity(raw.len());
+    for (_, x, y) in adam7_order(width, height) {
+        let idx = (y as usize * width as usize + x as usize) * bpp;
+        interlaced.extend_from_slice(&raw[idx..idx + bpp]);
+    }
+    interlaced
+}
+
+/// Reorder raw pixels from Adam7 pass order back into row order
+fn deinterlace_adam7(interlaced: &[u8], width: u32, height: u32, bpp: usize) -> Vec<u8> {
+    let mut raw = vec![0u8; width as usize * height as usize * bpp];
+    for ((_, x, y), pixel) in adam7_order(width, height).zip(interlaced.chunks_exact(bpp)) {
+        let idx = (y as usize * width as usize + x as usize) * bpp;
+        raw[idx..idx + bpp].copy_from_slice(pixel);
+    }
+    raw
+}
+
+/// Row-order pixels from a partial Adam7 stream, with every decoded pixel
+/// spread over the block it stands for until a later pass refines it
+///
+/// Returns the pixels and the number of complete passes.
+fn adam7_preview(partial: &[u8], width: u32, height: u32, bpp: usize) -> (Vec<u8>, usize) {
+    let mut raw = vec![0u8; width as usize * height as usize * bpp];
+    let mut complete_passes = 0;
+    let mut order = adam7_order(width, height).peekable();
+    
+    for pixel in partial.chunks_exact(bpp) {
+        let Some((pass, x, y)) = order.next() else { break };
+        let (block_width, block_height) = ADAM7_BLOCKS[pass];
+        for by in y..(y + block_height).min(height) {
+            for bx in x..(x + block_width).min(width) {
+                let idx = (by as usize * width as usize + bx as usize) * bpp;
+                raw[idx..idx + bpp].copy_from_slice(pixel);
+            }
+        }
+        if order.peek().is_none_or(|&(next_pass, _, _)| next_pass != pass) {
+            complete_passes = pass + 1;
+        }
+    }
+    (raw, complete_passes)
+}
+
+/// Coarse first frame of an interlaced file from a prefix of its bytes
+///
+/// The header, metadata and ICC profile must be complete; the frame data may
+/// be cut anywhere. The returned image has binary pixels in its only frame,
+/// along with the number of complete Adam7 passes.
+fn decode_progressive_preview(
+    prefix: &[u8],
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<(YeetImageV3, usize), std::io::Error> {
+    let mut reader = prefix;
+    let header = read_yeet_v3_header_from(&mut reader, dictionary)?;
+    if header.metadata.interlace != Interlace::Adam7 || header.metadata.tiles.is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Not an interlaced YEET file (compile with --interlace)"
+        ));
+    }
+    
+    let icc_data = read_v3_icc_profile(&mut reader)?;
+    let icc_profile = header.has_icc.then_some(icc_data);
+    
+    let delay = read_u32(&mut reader)?;
+    let data_len = read_u32(&mut reader)? as usize;
+    let available = &reader[..data_len.min(reader.len())];
+    
+    let data = decompress_prefix(available, header.compression, header.dictionary.as_ref())?;
+    let partial = if header.is_binary {
+        data
+    } else {
+        decode_hex_pixels(&data[..data.len() / 2 * 2])
+    };
+    let bpp = header.bytes_per_pixel();
+    let (raw, complete_passes) = adam7_preview(&partial, header.width, header.height, bpp);
+    
+    let image = YeetImageV3 {
+        width: header.width,
+        height: header.height,
+        has_alpha: header.has_alpha,
+        is_hdr: header.is_hdr,
+        is_binary: true,
+        compression: header.compression,
+        compression_level: None,
+        dictionary: header.dictionary,
+        loop_count: header.loop_count,
+        metadata: header.metadata,
+        thumbnail: header.thumbnail,
+        icc_profile,
+        frames: vec![YeetFrame { delay, data: raw }],
+    };
+    Ok((image, complete_passes))
+}
+
+// ============================================================================
+// v3 Reading & Viewing
+// ============================================================================
+
+/// Read the header, metadata and thumbnail without touching the frame data
+fn read_yeet_v3_header(path: &Path, dictionary: Option<&ZstdDictionary>) -> Result<YeetHeaderV3, std::io::Error> {
+    read_yeet_v3_header_from(&mut File::open(path)?, dictionary)
+}
+
+/// `dictionary` is the one given with `--dict`, for files that reference a
+/// dictionary instead of embedding it
+fn read_yeet_v3_header_from(
+    reader: &mut impl Read,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<YeetHeaderV3, std::io::Error> {
+    let raw = read_v3_header(reader)?;
+    let flags = raw.flags;
+    let metadata = YeetMetadataV3::from_json_lenient(&raw.metadata)?;
+    
+    // The metadata `codec` key overrides the flag bits (codecs added after v3.0)
+    let compression = match metadata.codec.as_deref() {
+        Some(name) => CompressionAlgorithm::from_name(name).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported codec '{}'", name)
+        ))?,
+        None => CompressionAlgorithm::from_flag_bits(flags),
+    };
+    
+    // Zstd dictionary: embedded after the metadata, or looked up by ID
+    let embedded = matches!(metadata.zstd_dictionary, Some(DictionaryRef { embedded: true, .. }));
+    let sections = read_v3_sections(reader, &raw, embedded)?;
+    let dictionary = match metadata.zstd_dictionary {
+        Some(DictionaryRef { id, embedded: true }) => {
+            let dictionary = ZstdDictionary::from_bytes(sections.dictionary.unwrap_or_default())?;
+            if dictionary.id != id {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Embedded zstd dictionary does not match its ID"
+                ));
+            }
+            Some(ZstdDictionary { embed: true, ..dictionary })
+        }
+        Some(DictionaryRef { id, embedded: false }) => Some(find_dictionary(id, dictionary).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Missing zstd dictionary {} (pass --dict <file> or list it in YEET_DICT_PATH)", id)
+        ))?),
+        None => None,
+    };
+    
+    let thumbnail = match sections.thumbnail {
+        Some(thumbnail) => {
+            let data = decompress_data(&thumbnail.data, compression, dictionary.as_ref())?;
+            if data.len() != (thumbnail.width as usize) * (thumbnail.height as usize) * 4 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Thumbnail size does not match its dimensions"
+                ));
+            }
+            Some(YeetThumbnail { width: thumbnail.width, height: thumbnail.height, data })
+        }
+        None => None,
+    };
+    
+    Ok(YeetHeaderV3 {
+        width: raw.width,
+        height: raw.height,
+        has_alpha: (flags & 0b00000100) != 0,
+        is_hdr: (flags & 0b01000000) != 0,
+        is_binary: (flags & 0b00001000) != 0,
+        has_icc: (flags & 0b00100000) != 0,
+        compression,
+        frame_count: raw.frame_count,
+        loop_count: raw.loop_count,
+        metadata,
+        dictionary,
+        thumbnail,
+    })
+}
+
+fn read_yeet_v3(path: &Path, dictionary: Option<&ZstdDictionary>) -> Result<YeetImageV3, std::io::Error> {
+    let mut file = File::open(path)?;
+    let header = read_yeet_v3_header_from(&mut file, dictionary)?;
+    
+    // Read ICC profile if present
+    let icc_data = read_v3_icc_profile(&mut file)?;
+    let icc_profile = if header.has_icc {
+        Some(icc_data)
+    } else {
+        if !icc_data.is_empty() {
+            eprintln!("[WARN] ICC data present but flag not set");
+        }
+        None
+    };
+    
+    // Read frames (the file is read in order, frames decompress in parallel)
+    let table = read_v3_frame_table(&mut file, header.frame_count, 0)?;
+    let mut payloads = Vec::new();
+    for chunk in &table.frames {
+        file.seek(SeekFrom::Start(chunk.offset))?;
+        payloads.push((chunk.delay.unwrap_or(0), read_bytes(&mut file, chunk.len as usize)?));
+    }
+    
+    let frames = payloads
+        .into_par_iter()
+        .map(|(delay, compressed_data)| {
+            let data = match header.metadata.tiles {
+                Some(layout) => decode_tiled_frame(&header, &compressed_data, layout)?,
+                None if header.metadata.interlace == Interlace::Adam7 => {
+                    let data = decompress_data(&compressed_data, header.compression, header.dictionary.as_ref())?;
+                    let raw = if header.is_binary { data } else { decode_hex_pixels(&data) };
+                    let raw = deinterlace_adam7(&raw, header.width, header.height, header.bytes_per_pixel());
+                    if header.is_binary { raw } else { encode_hex_pixels(&raw) }
+                }
+                None => decompress_data(&compressed_data, header.compression, header.dictionary.as_ref())?,
+            };
+            Ok(YeetFrame { delay, data })
+        })
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+    
+    if header.metadata.color_model == ColorModel::Cmyk && icc_profile.is_none() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "CMYK image without an embedded ICC profile"
+        ));
+    }
+    
+    println!("[INFO] Loaded YEET v3: {}x{}, {} frames", header.width, header.height, header.frame_count);
+    if icc_profile.is_some() {
+        println!("[INFO] ICC profile present");
+    }
+    
+    Ok(YeetImageV3 {
+        width: header.width,
+        height: header.height,
+        has_alpha: header.has_alpha,
+        is_hdr: header.is_hdr,
+        is_binary: header.is_binary,
+        compression: header.compression,
+        compression_level: None,
+        dictionary: header.dictionary,
+        loop_count: header.loop_count,
+        metadata: header.metadata,
+        thumbnail: header.thumbnail,
+        icc_profile,
+        frames,
+    })
+}
+
+/// Open a tiled and/or pyramid file for region decoding
+///
+/// The returned image carries everything but the pixels (frames have delays
+/// and no data); pixels come from `TiledReader::decode_region`.
+fn read_yeet_v3_tiled(
+    path: &Path,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<(YeetImageV3, TiledReader), std::io::Error> {
+    let mut file = File::open(path)?;
+    let header = read_yeet_v3_header_from(&mut file, dictionary)?;
+    
+    let icc_data = read_v3_icc_profile(&mut file)?;
+    let icc_profile = header.has_icc.then_some(icc_data);
+    if header.metadata.color_model == ColorModel::Cmyk && icc_profile.is_none() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "CMYK image without an embedded ICC profile"
+        ));
+    }
+    
+    // Read only the tile tables, skipping over the tile data
+    let table = read_v3_frame_table(&mut file, header.frame_count, header.metadata.pyramid_levels)?;
+    let read_level = |file: &mut File, width: u32, height: u32, chunks: &[V3Chunk]| {
+        let layout = header.metadata.tiles.unwrap_or(TileLayout { width, height });
+        let expected_tiles = layout.tile_count(width, height);
+        let mut tile_tables = Vec::new();
+        for chunk in chunks {
+            let tiles = if header.metadata.tiles.is_some() {
+                file.seek(SeekFrom::Start(chunk.offset))?;
+                let (tiles, table_len) = read_tile_table(file, expected_tiles)?;
+                let data_start = chunk.offset + table_len;
+                tiles.into_iter().map(|(offset, len)| (data_start + offset, len)).collect()
+            } else {
+                vec![(chunk.offset, chunk.len)]
+            };
+            tile_tables.push(tiles);
+        }
+        Ok::<_, std::io::Error>(TiledLevel { width, height, layout, frames: tile_tables })
+    };
+    
+    let mut levels = vec![read_level(&mut file, header.width, header.height, &table.frames)?];
+    for chunks in &table.levels {
+        let (width, height) = levels.last().map(|l| (l.width.div_ceil(2), l.height.div_ceil(2))).unwrap();
+        levels.push(read_level(&mut file, width, height, chunks)?);
+    }
+    
+    let layout = levels[0].layout;
+    println!("[INFO] Opened YEET v3 for region decoding: {}x{}, {} frames, {}x{} tiles, {} pyramid levels",
+             header.width, header.height, header.frame_count, layout.width, layout.height,
+             header.metadata.pyramid_levels);
+    
+    let reader = TiledReader {
+        file,
+        bytes_per_pixel: header.bytes_per_pixel(),
+        is_binary: header.is_binary,
+        compression: header.compression,
+        dictionary: header.dictionary.clone(),
+        interlace: header.metadata.interlace,
+        levels,
+        cache: std::collections::HashMap::new(),
+    };
+    let image = YeetImageV3 {
+        width: header.width,
+        height: header.height,
+        has_alpha: header.has_alpha,
+        is_hdr: header.is_hdr,
+        is_binary: header.is_binary,
+        compression: header.compression,
+        compression_level: None,
+        dictionary: header.dictionary,
+        loop_count: header.loop_count,
+        metadata: header.metadata,
+        thumbnail: header.thumbnail,
+        icc_profile,
+        frames: table.frames.iter()
+            .map(|chunk| YeetFrame { delay: chunk.delay.unwrap_or(0), data: Vec::new() })
+            .collect(),
+    };
+    Ok((image, reader))
+}
+
+fn yeet_v3_to_image(
+    yeet_img: &YeetImageV3,
+    frame_index: usize,
+    display_transform: Option<&DisplayTransform>,
+) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let data = frame_raw_pixels(yeet_img, frame_index);
+    pixels_to_image(yeet_img, data, yeet_img.width, yeet_img.height, display_transform)
+}
+
+/// Turn raw pixels (a whole frame or a decoded region) into a displayable RGBA image
+fn pixels_to_image(
+    yeet_img: &YeetImageV3,
+    mut data: Vec<u8>,
+    width: u32,
+    height: u32,
+    display_transform: Option<&DisplayTransform>,
+) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let mut color_model = yeet_img.metadata.color_model;
+    
+    // Apply ICC profile if present (always yields RGB(A))
+    if let Some(converted) = display_transform.and_then(|t| t.apply(&data)) {
+        data = converted;
+        color_model = ColorModel::Rgb;
+    }
+    
+    let color_channels = color_model.channels();
+    let channels = color_channels + usize::from(yeet_img.has_alpha);
+    let bytes_per_sample = if yeet_img.is_hdr { 2 } else { 1 };
+    let bytes_per_pixel = channels * bytes_per_sample;
+    
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let idx = ((y * width + x) as usize) * bytes_per_pixel;
+        // Big-endian 16-bit samples: the first byte is the 8-bit display value
+        let sample = |c: usize| data.get(idx + c * bytes_per_sample).copied().unwrap_or(0);
+        
+        // Rough preview for CMYK/Lab when no ICC transform could be built
+        let [r, g, b] = match color_model {
+            ColorModel::Rgb => [sample(0), sample(1), sample(2)],
+            ColorModel::Cmyk => {
+                let k = 255 - sample(3) as u16;
+                [0, 1, 2].map(|c| ((255 - sample(c) as u16) * k / 255) as u8)
+            }
+            ColorModel::Lab => [sample(0); 3],
+        };
+        let a = if yeet_img.has_alpha { sample(color_channels) } else { 255 };
+        Rgba([r, g, b, a])
+    })
+}
+
+// ============================================================================
+// GUI Application with Animation Support
+// ============================================================================
+
+struct YeetV3ViewerApp {
+    image: Option<YeetImageV3>,
+    current_frame: usize,
+    last_frame_time: std::time::Instant,
+    playing: bool,
+    loaded_image: Option<RetainedImage>,
+    color_options: ColorTransformOptions,
+    proof_profile: Option<(String, Vec<u8>)>,
+    proof_input: String,
+    soft_proofing: bool,
+    gamut_warning: bool,
+    display_settings: DisplaySettings,
+    display_transform: Option<DisplayTransform>,
+    // Tiled images are decoded one visible window at a time
+    tiles: Option<TiledReader>,
+    view_origin: egui::Vec2,
+    view_size: [u32; 2],
+    zoom: f32,
+    // Screen pixels per pixel of the loaded region (zoom × level scale)
+    loaded_scale: f32,
+    loaded_level: usize,
+}
+
+impl YeetV3ViewerApp {
+    fn new(
+        image: YeetImageV3,
+        color_options: ColorTransformOptions,
+        proof_profile: Option<(String, Vec<u8>)>,
+        gamut_warning: bool,
+        tiles: Option<TiledReader>,
+    ) -> Self {
+        let is_animated = image.frames.len() > 1;
+        let mut app = Self {
+            image: Some(image),
+            current_frame: 0,
+            last_frame_time: std::time::Instant::now(),
+            playing: is_animated,
+            loaded_image: None,
+            color_options,
+            proof_input: proof_profile.as_ref().map(|(name, _)| name.clone()).unwrap_or_default(),
+            soft_proofing: proof_profile.is_some(),
+            proof_profile,
+            gamut_warning,
+            display_settings: DisplaySettings { color: color_options, proofing: None },
+            display_transform: None,
+            tiles,
+            view_origin: egui::Vec2::ZERO,
+            view_size: [1024, 768],
+            zoom: 1.0,
+            loaded_scale: 1.0,
+            loaded_level: 0,
+        };
+        app.display_settings = app.current_display_settings();
+        app.display_transform = app.image
+            .as_ref()
+            .and_then(|img| DisplayTransform::new(img, &app.display_settings));
+        app
+    }
+    
+    fn current_display_settings(&self) -> DisplaySettings {
+        let proofing = match self.proof_profile {
+            Some((ref name, ref profile)) if self.soft_proofing => Some(ProofingSettings {
+                name: name.clone(),
+                profile: profile.clone(),
+                gamut_warning: self.gamut_warning,
+            }),
+            _ => None,
+        };
+        DisplaySettings { color: self.color_options, proofing }
+    }
+    
+    /// Rebuild the cached ICC transform when any display setting changed
+    fn refresh_display_transform(&mut self) {
+        let settings = self.current_display_settings();
+        if settings == self.display_settings {
+            return;
+        }
+        self.display_settings = settings;
+        
+        if let Some(ref img) = self.image {
+            self.display_transform = DisplayTransform::new(img, &self.display_settings);
+            self.update_current_frame();
+        }
+    }
+    
+    /// Load the proofing profile typed into the viewer (name or `.icc` path)
+    fn load_proof_profile(&mut self) {
+        match load_profile_arg(self.proof_input.trim()) {
+            Ok(profile) => {
+                let name = icc_description(&profile)
+                    .unwrap_or_else(|| self.proof_input.trim().to_string());
+                println!("[INFO] Loaded proofing profile: {}", name);
+                self.proof_profile = Some((name, profile));
+                self.soft_proofing = true;
+            }
+            Err(e) => eprintln!("[ERROR] {}", e),
+        }
+    }
+    
+    /// Change the zoom of the region view, keeping the center of the view in place
+    fn set_zoom(&mut self, zoom: f32) {
+        let levels = self.tiles.as_ref().map_or(1, TiledReader::level_count);
+        let zoom = zoom.clamp(1.0 / (1u32 << levels) as f32, 16.0);
+        let half_view = egui::vec2(self.view_size[0] as f32, self.view_size[1] as f32) / 2.0;
+        let center = self.view_origin + half_view / self.zoom;
+        self.view_origin = (center - half_view / zoom).max(egui::Vec2::ZERO);
+        self.zoom = zoom;
+    }
+    
+    fn update_current_frame(&mut self) {
+        if let Some(ref img) = self.image {
+            let mut scale = 1.0;
+            let mut level = 0;
+            let frame_img = match self.tiles {
+                Some(ref mut tiles) => {
+                    // Coarsest pyramid level that still has a pixel per screen pixel
+                    while level + 1 < tiles.level_count() && self.zoom * (2u32 << level) as f32 <= 1.0 {
+                        level += 1;
+                    }
+                    let level_scale = (1u32 << level) as f32;
+                    scale = self.zoom * level_scale;
+                    
+                    // The view is in display coordinates; decode the stored region behind it
+                    let orientation = img.metadata.orientation();
+                    let stored_size = tiles.level_size(level);
+                    let (level_width, level_height) = orientation.display_size(stored_size.0, stored_size.1);
+                    let x = ((self.view_origin.x / level_scale) as u32).min(level_width);
+                    let y = ((self.view_origin.y / level_scale) as u32).min(level_height);
+                    let width = ((self.view_size[0] as f32 / scale).ceil() as u32).min(level_width - x);
+                    let height = ((self.view_size[1] as f32 / scale).ceil() as u32).min(level_height - y);
+                    let (x, y, width, height) = orientation.source_rect(x, y, width, height, stored_size);
+                    match tiles.decode_level_region(level, self.current_frame, x, y, width, height) {
+                        Ok(data) => orientation.apply_to_image(
+                            pixels_to_image(img, data, width, height, self.display_transform.as_ref())
+                        ),
+                        Err(e) => {
+                            eprintln!("[ERROR] Failed to decode region: {}", e);
+                            return;
+                        }
+                    }
+                }
+                None => img.metadata.orientation().apply_to_image(
+                    yeet_v3_to_image(img, self.current_frame, self.display_transform.as_ref())
+                ),
+            };
+            
+            // Save to temp file for RetainedImage
+            frame_img.save(TEMP_RESULT_PATH).ok();
+            
+            if let Ok(retained) = RetainedImage::from_image_bytes(
+                "temp_frame",
+                &std::fs::read(TEMP_RESULT_PATH).unwrap()
+            ) {
+                self.loaded_image = Some(retained);
+                self.loaded_scale = scale;
+                self.loaded_level = level;
+            }
+        }
+    }
+}
+
+impl eframe::App for YeetV3ViewerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.loaded_image.is_none() {
+            self.update_current_frame();
+        }
+        
+        // Handle animation
+        if let Some(ref img) = self.image {
+            if self.playing && img.frames.len() > 1 {
+                let current_delay = img.frames[self.current_frame].delay;
+                let delay_ms = if current_delay > 0 { current_delay } else { 100 }; // Default 100ms
+                
+                if self.last_frame_time.elapsed().as_millis() >= delay_ms as u128 {
+                    self.current_frame = (self.current_frame + 1) % img.frames.len();
+                    self.update_current_frame();
+                    self.last_frame_time = std::time::Instant::now();
+                }
+                
+                // Request repaint for smooth animation
+                ctx.request_repaint();
+            }
+        }
+        
+        let mut frame_changed = false;
+        let mut load_proof = false;
+        let mut zoom_request = None;
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(ref img) = self.image {
+                let (display_width, display_height) = img.metadata.orientation().display_size(img.width, img.height);
+                
+                // Info panel
+                ui.horizontal(|ui| {
+                    ui.heading("YEET v3 Viewer");
+                    ui.separator();
+                    ui.label(format!("{}x{}", display_width, display_height));
+                    
+                    if img.frames.len() > 1 {
+                        ui.separator();
+                        ui.label(format!("Frame {}/{}", self.current_frame + 1, img.frames.len()));
+                        
+                        if ui.button(if self.playing { "⏸ Pause" } else { "▶ Play" }).clicked() {
+                            self.playing = !self.playing;
+                        }
+                        
+                        if ui.button("⏮ Prev").clicked() {
+                            self.current_frame = if self.current_frame == 0 {
+                                img.frames.len() - 1
+                            } else {
+                                self.current_frame - 1
+                            };
+                            frame_changed = true;
+                        }
+                        
+                        if ui.button("⏭ Next").clicked() {
+                            self.current_frame = (self.current_frame + 1) % img.frames.len();
+                            frame_changed = true;
+                        }
+                    }
+                    
+                    if img.icc_profile.is_some() {
+                        ui.separator();
+                        ui.label("🎨 ICC Profile");
+                    }
+                    
+                    if self.tiles.is_some() {
+                        ui.separator();
+                        ui.label(format!("🧩 View at {},{} (drag to pan)",
+                            self.view_origin.x as u32, self.view_origin.y as u32));
+                        
+                        ui.separator();
+                        if ui.button("➖").clicked() {
+                            zoom_request = Some(self.zoom / 2.0);
+                        }
+                        ui.label(format!("{:.0}%", self.zoom * 100.0));
+                        if ui.button("➕").clicked() {
+                            zoom_request = Some(self.zoom * 2.0);
+                        }
+                        if ui.button("1:1").clicked() {
+                            zoom_request = Some(1.0);
+                        }
+                        if ui.button("Fit").clicked() {
+                            let [view_width, view_height] = self.view_size;
+                            zoom_request = Some(
+                                (view_width as f32 / display_width as f32).min(view_height as f32 / display_height as f32)
+                            );
+                        }
+                        if self.loaded_level > 0 {
+                            ui.label(format!("(pyramid level {})", self.loaded_level));
+                        }
+                    }
+                });
+                
+                // Color management controls
+                ui.horizontal(|ui| {
+                    ui.label("Intent:");
+                    egui::ComboBox::from_id_source("rendering_intent")
+                        .selected_text(self.color_options.intent.name())
+                        .show_ui(ui, |ui| {
+                            for intent in RenderingIntent::ALL {
+                                ui.selectable_value(&mut self.color_options.intent, intent, intent.name());
+                            }
+                        });
+                    ui.checkbox(&mut self.color_options.black_point_compensation, "BPC");
+                    
+                    ui.separator();
+                    ui.label("Proof:");
+                    let input = ui.text_edit_singleline(&mut self.proof_input);
+                    let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("Load").clicked() || submitted {
+                        load_proof = true;
+                    }
+                    
+                    ui.add_enabled_ui(self.proof_profile.is_some(), |ui| {
+                        ui.checkbox(&mut self.soft_proofing, "Soft proof");
+                        ui.add_enabled(
+                            self.soft_proofing,
+                            egui::Checkbox::new(&mut self.gamut_warning, "Gamut warning"),
+                        );
+                    });
+                });
+                
+                ui.separator();
+                
+                // Metadata panel (collapsible)
+                ui.collapsing("📊 Metadata", |ui| {
+                    egui::Grid::new("metadata_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 5.0])
+                        .show(ui, |ui| {
+                            if let Some(ref author) = img.metadata.author {
+                                ui.label("Author:");
+                                ui.label(author);
+                                ui.end_row();
+                            }
+                            if let Some(ref created) = img.metadata.created {
+                                ui.label("Created:");
+                                ui.label(created);
+                                ui.end_row();
+                            }
+                            ui.label("Software:");
+                            ui.label(&img.metadata.software);
+                            ui.end_row();
+                            
+                            if let Some(ref color_space) = img.metadata.color_space {
+                                ui.label("Color Space:");
+                                ui.label(color_space);
+                                ui.end_row();
+                            }
+                            
+                            if img.metadata.color_model != ColorModel::Rgb {
+                                ui.label("Color Model:");
+                                ui.label(img.metadata.color_model.name());
+                                ui.end_row();
+                            }
+                            
+                            ui.label("Bit Depth:");
+                            ui.label(format!("{}-bit", img.metadata.bit_depth));
+                            ui.end_row();
+                            
+                            if img.metadata.quality != Quality::Lossless {
+                                ui.label("Quality:");
+                                ui.label(img.metadata.quality.describe());
+                                ui.end_row();
+                            }
+                            
+                            if img.metadata.orientation() != Orientation::Normal {
+                                ui.label("Orientation:");
+                                ui.label(img.metadata.orientation().name());
+                                ui.end_row();
+                            }
+                            
+                            if let Some((x_dpi, y_dpi)) = img.metadata.dpi {
+                                ui.label("Resolution:");
+                                ui.label(format!("{}x{} dpi", x_dpi, y_dpi));
+                                ui.end_row();
+                                
+                                if let Some(size) = physical_size_inches(&img.metadata, display_width, display_height) {
+                                    ui.label("Print Size:");
+                                    ui.label(describe_physical_size(size));
+                                    ui.end_row();
+                                }
+                            }
+                            
+                            if let Some(ref camera) = img.metadata.camera {
+                                ui.label("Camera:");
+                                ui.label(camera.summary());
+                                ui.end_row();
+                            }
+                            
+                            for (keyword, value) in &img.metadata.text {
+                                ui.label(format!("{}:", keyword));
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                            
+                            if let Some(ref xmp) = img.metadata.xmp {
+                                ui.label("XMP:");
+                                ui.label(format!("{} bytes", xmp.len()));
+                                ui.end_row();
+                            }
+                            
+                            if let Some(ref thumbnail) = img.thumbnail {
+                                ui.label("Thumbnail:");
+                                ui.label(format!("{}x{}", thumbnail.width, thumbnail.height));
+                                ui.end_row();
+                            }
+                            
+                            if img.frames.len() > 1 {
+                                ui.label("Total Frames:");
+                                ui.label(format!("{}", img.frames.len()));
+                                ui.end_row();
+                                
+                                if let Some(delay) = img.metadata.frame_delay {
+                                    ui.label("Frame Delay:");
+                                    ui.label(format!("{}ms", delay));
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                });
+                
+                ui.separator();
+                
+                // Image display
+                if self.tiles.is_some() {
+                    // Only the visible window is decoded; dragging moves it over the image
+                    let available = ui.available_size();
+                    let (rect, response) = ui.allocate_exact_size(available, egui::Sense::drag());
+                    let view_size = [available.x.max(1.0) as u32, available.y.max(1.0) as u32];
+                    
+                    let zoom_delta = ui.input(|i| i.zoom_delta());
+                    if zoom_delta != 1.0 {
+                        zoom_request = Some(self.zoom * zoom_delta);
+                    }
+                    
+                    let max_origin = egui::vec2(
+                        (display_width as f32 - view_size[0] as f32 / self.zoom).max(0.0),
+                        (display_height as f32 - view_size[1] as f32 / self.zoom).max(0.0),
+                    );
+                    let origin = (self.view_origin - response.drag_delta() / self.zoom)
+                        .clamp(egui::Vec2::ZERO, max_origin);
+                    if origin != self.view_origin || view_size != self.view_size {
+                        self.view_origin = origin;
+                        self.view_size = view_size;
+                        frame_changed = true;
+                    }
+                    
+                    if let Some(ref retained_img) = self.loaded_image {
+                        ui.painter_at(rect).image(
+                            retained_img.texture_id(ctx),
+                            egui::Rect::from_min_size(rect.min, retained_img.size_vec2() * self.loaded_scale),
+                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                            egui::Color32::WHITE,
+                        );
+                    }
+                } else {
+                    egui::ScrollArea::both()
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            if let Some(ref retained_img) = self.loaded_image {
+                                retained_img.show(ui);
+                            }
+                        });
+                }
+            }
+        });
+        
+        if load_proof {
+            self.load_proof_profile();
+        }
+        if let Some(zoom) = zoom_request {
+            self.set_zoom(zoom);
+            frame_changed = true;
+        }
+        if frame_changed {
+            self.update_current_frame();
+        }
+        self.refresh_display_transform();
+    }
+}
+
+// ============================================================================
+// Main Entry Point
+// ============================================================================
+
+fn main() -> Result<(), eframe::Error> {
+    env_logger::init();
+    
+    let args: Vec<String> = env::args().collect();
+    let args = &args;
+    
+    if args.len() < 2 {
+        print_usage(&args[0]);
+        std::process::exit(1);
+    }
+    
+    println!("[INFO] YEET v3 - Advanced Features Enabled");
+    println!("  ✅ Brotli/Zstd compression");
+    println!("  ✅ ICC color profiles");
+    println!("  ✅ Multi-frame animation");
+    println!();
+    
+    // Worker threads for (de)compression; the output does not depend on it
+    if let Some(threads) = arg_value(args, "--threads") {
+        let pool = threads.parse::<usize>().ok().filter(|&n| n > 0).map(|n| {
+            rayon::ThreadPoolBuilder::new().num_threads(n).build_global()
+        });
+        if !matches!(pool, Some(Ok(()))) {
+            eprintln!("[ERROR] Invalid thread count '{}'", threads);
+            std::process::exit(1);
+        }
+    }
+    
+    let command = &args[1];
+    
+    if args.contains(&"--dict".to_string()) && NO_DICTIONARY_COMMANDS.contains(&command.as_str()) {
+        eprintln!("[ERROR] '{}' does not use --dict", command);
+        std::process::exit(1);
+    }
+    
+    match command.as_str() {
+        "compile" => {
+            if args.len() < 3 {
+                eprintln!("[ERROR] No input file");
+                std::process::exit(1);
+            }
+            
+            let path: PathBuf = args[2].clone().into();
+            let options = match CompileOptions::from_args(args) {
+                Ok(o) => o,
+                Err(e) => {
+                    eprintln!("[ERROR] {}", e);
+                    std::process::exit(1);
+                }
+            };
+            
+            match png_to_yeet_v3(path, &options) {
+                Ok(()) => println!("[OK] Conversion complete"),
+                Err(e) => eprintln!("[ERROR] {}", e),
+            }
+            Ok(())
+        }
+        "extract-profile" => handle_extract_profile(args),
+        "embed-profile" => handle_embed_profile(args),
+        "strip-profile" => handle_strip_profile(args),
+        "convert-profile" => handle_convert_profile(args),
+        "batch" => handle_batch(args),
+        "optimize" => handle_optimize(args),
+        "train-dict" => handle_train_dict(args),
+        "meta" => handle_meta(args),
+        "extract-xmp" => handle_extract_xmp(args),
+        "embed-xmp" => handle_embed_xmp(args),
+        "thumbnail" => handle_thumbnail(args),
+        "preview" => handle_preview(args),
+        "export" => handle_export(args),
+        "help" | "--help" | "-h" => {
+            print_usage(&args[0]);
+            Ok(())
+        }
+        _ => {
+            // Try to view the file
+            let path: PathBuf = args[1].clone().into();
+            
+            let color_options = match ColorTransformOptions::from_args(args) {
+                Ok(o) => o,
+                Err(e) => {
+                    eprintln!("[ERROR] {}", e);
+                    std::process::exit(1);
+                }
+            };
+            
+            let proof_profile = match arg_value(args, "--proof") {
+                Some(name) => match load_profile_arg(name) {
+                    Ok(profile) => {
+                        let description = icc_description(&profile).unwrap_or_else(|| name.to_string());
+                        Some((description, profile))
+                    }
+                    Err(e) => {
+                        eprintln!("[ERROR] {}", e);
+                        std::process::exit(1);
+                    }
+                },
+                None => None,
+            };
+            let gamut_warning = args.contains(&"--gamut-warning".to_string());
+            
+            // Tiled and pyramid files are opened for region decoding instead of being loaded whole
+            let dictionary = dictionary_arg(args);
+            let loaded = match read_yeet_v3_header(&path, dictionary.as_ref()) {
+                Ok(header) if header.metadata.tiles.is_some() || header.metadata.pyramid_levels > 0 => {
+                    read_yeet_v3_tiled(&path, dictionary.as_ref()).map(|(img, tiles)| (img, Some(tiles)))
+                }
+                _ => read_yeet_v3(&path, dictionary.as_ref()).map(|img| (img, None)),
+            };
+            
+            match loaded {
+                Ok((img, tiles)) => {
+                    let options = eframe::NativeOptions {
+                        resizable: true,
+                        initial_window_size: Some(egui::vec2(1024.0, 768.0)),
+                        ..Default::default()
+                    };
+                    
+                    eframe::run_native(
+                        &format!("YEET v3 Viewer - {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()),
+                        options,
+                        Box::new(move |_cc| Box::new(YeetV3ViewerApp::new(
+                            img,
+                            color_options,
+                            proof_profile,
+                            gamut_warning,
+                            tiles,
+                        ))),
+                    )
+                }
+                Err(e) => {
+                    eprintln!("[ERROR] Failed to load file: {}", e);
+                    eprintln!("Tip: Use 'compile' command to convert PNG to YEET v3");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Value following a `--flag value` pair on the command line
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.as_str())
+}
+
+/// Commands that never compress or decompress pixel data, so `--dict` is an error
+const NO_DICTIONARY_COMMANDS: &[&str] = &["train-dict", "meta", "extract-xmp", "embed-xmp"];
+
+/// Dictionary given with `--dict`, for reading files that reference one (exits if it cannot be loaded)
+fn dictionary_arg(args: &[String]) -> Option<ZstdDictionary> {
+    let path = arg_value(args, "--dict")?;
+    match load_dictionary(Path::new(path)) {
+        Ok(dictionary) => Some(dictionary),
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load dictionary {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Values of every `--flag value` pair for a repeatable flag
+fn arg_values<'a>(args: &'a [String], flag: &'a str) -> impl Iterator<Item = &'a str> {
+    args.windows(2)
+        .filter(move |pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+}
+
+// ============================================================================
+// ICC Profile Commands
+// ============================================================================
+
+/// Load the input file of a profile command, exiting with usage on error
+fn load_profile_command_input(args: &[String], usage: &str) -> (PathBuf, YeetImageV3) {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} {}", args[0], usage);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    match read_yeet_v3(&path, dictionary_arg(args).as_ref()) {
+        Ok(img) => (path, img),
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load file: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Write a rewritten file to `--output` (or back over the input) and report the result
+///
+/// An embedded thumbnail is re-rendered at its old size, since the pixels or
+/// their profile may have changed.
+fn finish_profile_command(args: &[String], input: &Path, yeet_img: &mut YeetImageV3) -> Result<(), eframe::Error> {
+    if let Some(max_edge) = yeet_img.thumbnail.as_ref().map(|t| t.width.max(t.height)) {
+        yeet_img.thumbnail = Some(make_thumbnail(yeet_img, max_edge));
+    }
+    
+    let output = arg_value(args, "--output").map(PathBuf::from).unwrap_or_else(|| input.to_path_buf());
+    match write_yeet_v3(&output, yeet_img) {
+        Ok(()) => {
+            println!("[OK] Wrote {}", output.display());
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle 'extract-profile' command
+fn handle_extract_profile(args: &[String]) -> Result<(), eframe::Error> {
+    let (path, yeet_img) = load_profile_command_input(args, "extract-profile <file.yeet> [--output profile.icc]");
+    
+    let Some(profile) = yeet_img.icc_profile else {
+        eprintln!("[ERROR] {} has no embedded ICC profile", path.display());
+        std::process::exit(1);
+    };
+    
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.with_extension("icc"));
+    
+    if let Err(e) = fs::write(&output, &profile) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Extracted ICC profile: {}", output.display());
+    println!("  Size: {} bytes", profile.len());
+    if let Some(description) = icc_description(&profile) {
+        println!("  Description: {}", description);
+    }
+    Ok(())
+}
+
+/// Handle 'embed-profile' command (tags the pixels without converting them)
+fn handle_embed_profile(args: &[String]) -> Result<(), eframe::Error> {
+    let usage = "embed-profile <file.yeet> <profile.icc|name> [--output out.yeet]";
+    let (path, mut yeet_img) = load_profile_command_input(args, usage);
+    
+    let Some(profile_arg) = args.get(3) else {
+        eprintln!("[ERROR] No profile specified");
+        eprintln!("Usage: {} {}", args[0], usage);
+        std::process::exit(1);
+    };
+    
+    let profile = match load_profile_arg(profile_arg) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    if let Err(e) = check_profile_color_model(&profile, yeet_img.metadata.color_model) {
+        eprintln!("[ERROR] {}", e);
+        std::process::exit(1);
+    }
+    
+    yeet_img.metadata.color_profile = Some(
+        icc_description(&profile).unwrap_or_else(|| "embedded".to_string())
+    );
+    yeet_img.icc_profile = Some(profile);
+    
+    finish_profile_command(args, &path, &mut yeet_img)
+}
+
+/// Handle 'strip-profile' command
+fn handle_strip_profile(args: &[String]) -> Result<(), eframe::Error> {
+    let (path, mut yeet_img) = load_profile_command_input(args, "strip-profile <file.yeet> [--output out.yeet]");
+    
+    if yeet_img.icc_profile.is_none() {
+        println!("[INFO] No ICC profile embedded, nothing to strip");
+        return Ok(());
+    }
+    if yeet_img.metadata.color_model == ColorModel::Cmyk {
+        eprintln!("[ERROR] CMYK images require an embedded ICC profile");
+        std::process::exit(1);
+    }
+    
+    yeet_img.icc_profile = None;
+    yeet_img.metadata.color_profile = None;
+    
+    finish_profile_command(args, &path, &mut yeet_img)
+}
+
+/// Handle 'convert-profile' command (untagged files are treated as sRGB)
+///
+/// The color model follows the target profile, so this also converts RGB ↔ CMYK.
+fn handle_convert_profile(args: &[String]) -> Result<(), eframe::Error> {
+    let usage = "convert-profile <file.yeet> <profile.icc|name> [--output out.yeet]";
+    let (path, mut yeet_img) = load_profile_command_input(args, usage);
+    
+    let Some(profile_arg) = args.get(3) else {
+        eprintln!("[ERROR] No target profile specified");
+        eprintln!("Usage: {} {}", args[0], usage);
+        std::process::exit(1);
+    };
+    
+    let color_options = match ColorTransformOptions::from_args(args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let source_model = yeet_img.metadata.color_model;
+    let source = match yeet_img.icc_profile.clone() {
+        Some(profile) => Ok(profile),
+        None if source_model == ColorModel::Lab => lab_profile_icc(),
+        None => load_profile_arg("sRGB"),
+    };
+    let (source, target) = match (source, load_profile_arg(profile_arg)) {
+        (Ok(source), Ok(target)) => (source, target),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let Some(target_model) = profile_color_model(&target) else {
+        eprintln!("[ERROR] Target profile is not an RGB, CMYK or Lab profile");
+        std::process::exit(1);
+    };
+    
+    let from_format = icc_pixel_format(source_model, yeet_img.has_alpha, yeet_img.is_hdr);
+    let to_format = icc_pixel_format(target_model, yeet_img.has_alpha, yeet_img.is_hdr);
+    for index in 0..yeet_img.frames.len() {
+        let raw = frame_raw_pixels(&yeet_img, index);
+        let raw = match convert_icc_pixels(&raw, &source, from_format, &target, to_format, color_options) {
+            Ok(raw) => raw,
+            Err(e) => {
+                eprintln!("[ERROR] {}", e);
+                std::process::exit(1);
+            }
+        };
+        yeet_img.frames[index].data = if yeet_img.is_binary { raw } else { encode_hex_pixels(&raw) };
+    }
+    
+    let description = icc_description(&target).unwrap_or_else(|| "embedded".to_string());
+    println!("[INFO] Converted {} frame(s) to {}", yeet_img.frames.len(), description);
+    if target_model != source_model {
+        println!("[INFO] Color model: {} → {}", source_model.name(), target_model.name());
+        yeet_img.metadata.color_model = target_model;
+        yeet_img.metadata.color_space = Some(target_model.name().to_string());
+    }
+    yeet_img.metadata.color_profile = Some(description);
+    yeet_img.icc_profile = Some(target);
+    
+    finish_profile_command(args, &path, &mut yeet_img)
+}
+
+// ============================================================================
+// Preview Commands
+// ============================================================================
+
+/// Handle 'thumbnail' command (reads only the header, never the frame data)
+fn handle_thumbnail(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} thumbnail <file.yeet> [--output thumb.png]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    let header = match read_yeet_v3_header(&path, dictionary_arg(args).as_ref()) {
+        Ok(header) => header,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load file: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let Some(thumbnail) = header.thumbnail else {
+        eprintln!("[ERROR] {} has no embedded thumbnail (compile with --thumbnail)", path.display());
+        std::process::exit(1);
+    };
+    
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.with_extension("thumb.png"));
+    
+    let Some(image) = ImageBuffer::<Rgba<u8>, _>::from_raw(thumbnail.width, thumbnail.height, thumbnail.data) else {
+        eprintln!("[ERROR] Corrupt thumbnail");
+        std::process::exit(1);
+    };
+    let image = header.metadata.orientation().apply_to_image(image);
+    if let Err(e) = image.save(&output) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    let (width, height) = header.metadata.orientation().display_size(header.width, header.height);
+    println!("[OK] Extracted thumbnail: {}", output.display());
+    println!("  Thumbnail: {}x{} (image {}x{})", image.width(), image.height(), width, height);
+    Ok(())
+}
+
+/// Handle 'export' command: render a frame to PNG as the viewer shows it
+///
+/// Color is converted to sRGB and the `orientation` is applied.
+fn handle_export(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} export <file.yeet> [--output out.png] [--frame n]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    let yeet_img = match read_yeet_v3(&path, dictionary_arg(args).as_ref()) {
+        Ok(img) => img,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load file: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let frame = match arg_value(args, "--frame").map(str::parse::<usize>) {
+        None => 0,
+        Some(Ok(frame)) if (1..=yeet_img.frames.len()).contains(&frame) => frame - 1,
+        Some(_) => {
+            eprintln!("[ERROR] Invalid frame (expected 1 to {})", yeet_img.frames.len());
+            std::process::exit(1);
+        }
+    };
+    
+    let settings = DisplaySettings {
+        color: ColorTransformOptions::default(),
+        proofing: None,
+    };
+    let transform = DisplayTransform::new(&yeet_img, &settings);
+    let orientation = yeet_img.metadata.orientation();
+    let image = orientation.apply_to_image(yeet_v3_to_image(&yeet_img, frame, transform.as_ref()));
+    
+    // pHYs follows the displayed axes
+    let dpi = yeet_img.metadata.dpi.map(|(x, y)| if orientation.swaps_axes() { (y, x) } else { (x, y) });
+    
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.with_extension("png"));
+    let mut text = yeet_img.metadata.text.clone();
+    if let Some(ref xmp) = yeet_img.metadata.xmp {
+        text.insert(XMP_PNG_KEYWORD.to_string(), xmp.clone());
+    }
+    if let Err(e) = save_png(&image, &output, dpi, &text) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Exported: {}", output.display());
+    println!("  Dimensions: {}x{}", image.width(), image.height());
+    if let Some((x_dpi, y_dpi)) = dpi {
+        println!("  DPI: {}x{}", x_dpi, y_dpi);
+    }
+    if orientation != Orientation::Normal {
+        println!("  Orientation: {} (applied)", orientation.name());
+    }
+    if !text.is_empty() {
+        println!("  Text chunks: {}", text.len());
+    }
+    Ok(())
+}
+
+/// Handle 'preview' command: render an interlaced file from its first `--bytes` bytes
+fn handle_preview(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} preview <file.yeet> [--bytes n] [--output preview.png]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let path = PathBuf::from(&args[2]);
+    let limit = match arg_value(args, "--bytes").map(str::parse::<u64>) {
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => {
+            eprintln!("[ERROR] Invalid byte count");
+            std::process::exit(1);
+        }
+        None => u64::MAX,
+    };
+    
+    // Only read the prefix, as a loader on a slow mount would have it
+    let mut prefix = Vec::new();
+    let read = File::open(&path).and_then(|file| file.take(limit).read_to_end(&mut prefix));
+    if let Err(e) = read {
+        eprintln!("[ERROR] Failed to load file: {}", e);
+        std::process::exit(1);
+    }
+    
+    let (yeet_img, complete_passes) = match decode_progressive_preview(&prefix, dictionary_arg(args).as_ref()) {
+        Ok(preview) => preview,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let settings = DisplaySettings {
+        color: ColorTransformOptions::default(),
+        proofing: None,
+    };
+    let transform = DisplayTransform::new(&yeet_img, &settings);
+    let image = yeet_img.metadata.orientation().apply_to_image(yeet_v3_to_image(&yeet_img, 0, transform.as_ref()));
+    
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.with_extension("preview.png"));
+    if let Err(e) = image.save(&output) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Wrote preview: {}", output.display());
+    println!("  Read: {} bytes", prefix.len());
+    println!("  Complete passes: {}/{}", complete_passes, ADAM7_PASSES.len());
+    Ok(())
+}
+
+fn print_usage(program: &str) {
     println!("╔═══════════════════════════════════════════════════════════╗");
     println!("║          YEET v3 - Next Generation Image Format          ║");
     println!("╚═══════════════════════════════════════════════════════════╝");
     println!();
     println!("USAGE:");
-    println!("  {} <file.yeet>                    View YEET v3 file", program);
+    println!("  {} <file.yeet> [viewer options]   View YEET v3 file", program);
     println!("  {} compile <file.png> [options]  Convert PNG to YEET v3", program);
+    println!("  {} batch <directory> [options]   Convert a folder tree in parallel", program);
+    println!("  {} optimize <file.yeet> [--level <n>] [--output out.yeet]", program);
+    println!("      Re-encode as small as possible (codec, strips/single stream/Adam7)");
+    println!("  {} train-dict <directory> --output set.dict  Train a zstd dictionary", program);
+    println!("  {} thumbnail <file.yeet> [--output thumb.png]", program);
+    println!("  {} preview <file.yeet> [--bytes n] [--output preview.png]", program);
+    println!("  {} export <file.yeet> [--frame n] [--output out.png]  Render to PNG (sRGB, oriented)", program);
+    println!();
+    println!("ICC PROFILE COMMANDS:");
+    println!("  {} extract-profile <file.yeet> [--output profile.icc]", program);
+    println!("  {} embed-profile <file.yeet> <profile> [--output out.yeet]", program);
+    println!("  {} strip-profile <file.yeet> [--output out.yeet]", program);
+    println!("  {} convert-profile <file.yeet> <profile> [--output out.yeet]", program);
+    println!();
+    println!("  <profile> is a path to an .icc file or one of the built-in names:");
+    println!("  {}", BUILTIN_PROFILES.join(", "));
+    println!();
+    println!("METADATA COMMANDS (pixel data is copied unchanged):");
+    println!("  {} meta show <file.yeet>", program);
+    println!("  {} meta set <file.yeet> key=value [...] [--output out.yeet]", program);
+    println!("  {} meta rm <file.yeet> key [...] [--output out.yeet]", program);
+    println!("  {} meta strip <file.yeet> [--output out.yeet]", program);
+    println!("  PNG text chunks are keys text.<keyword> (e.g. text.Title=\"Harbour\")");
+    println!("  Application data goes under custom.<key> (value is JSON or a string)");
+    println!();
+    println!("XMP COMMANDS:");
+    println!("  {} extract-xmp <file.yeet> [--output sidecar.xmp]", program);
+    println!("  {} embed-xmp <file.yeet> <sidecar.xmp> [--output out.yeet]", program);
     println!();
     println!("COMPRESSION OPTIONS:");
     println!("  --compress    Use zlib compression (v2 compatible)");
     println!("  --brotli      Use Brotli compression ✨ NEW!");
     println!("  --zstd        Use Zstd compression ✨ NEW!");
+    println!("  --lz4         Use LZ4 compression (fastest decoding)");
+    println!("  --lzma, --xz  Use LZMA (xz) compression");
+    println!("  --codec <c>   Use any codec by name: {}",
+             CODECS.iter().map(|codec| codec.name).collect::<Vec<_>>().join(", "));
     println!("  --binary      Binary encoding (recommended)");
+    println!("  --level <n>   Compression level: zlib 0-9 (default 9), brotli 0-11");
+    println!("                (default 11), zstd 1-22 (default 19, negative = fastest),");
+    println!("                lzma 0-9 (default 6); lz4 has no levels");
+    println!("  --auto        Try hex and binary with every algorithm and frame layout");
+    println!("                (strips, single stream, Adam7 - YEET has no pixel pre-filters,");
+    println!("                the layouts take their place); keep the smallest");
+    println!("                (--level applies to codecs that accept it)");
+    println!("  --dict <file> zstd dictionary from train-dict (files reference it by ID;");
+    println!("                readers need --dict or YEET_DICT_PATH to decode them)");
+    println!("  --embed-dict  Store the dictionary in the file instead");
+    println!("  --dict-size <bytes>  train-dict target size (default {})", DEFAULT_DICTIONARY_SIZE);
+    println!();
+    println!("PREVIEW OPTIONS:");
+    println!("  --thumbnail            Embed a {}px thumbnail for instant previews", THUMBNAIL_MAX_EDGE);
+    println!("  --thumbnail-size <px>  Embed a thumbnail with a custom longest edge");
+    println!();
+    println!("LAYOUT OPTIONS:");
+    println!("  --tile-size <n|wxh>    Store independently compressed tiles for");
+    println!("                         region decoding (viewer pans large images)");
+    println!("  --pyramid              Store half, quarter, ... resolution levels down");
+    println!("                         to {}px for cheap zoomed-out views", PYRAMID_MIN_EDGE);
+    println!("  --pyramid-levels <n>   Store exactly <n> pyramid levels");
+    println!("  --interlace            Adam7 pixel order for coarse-to-fine loading");
+    println!("  --strip-rows <n>       Use <n> rows per strip (default: {}-row strips,", DEFAULT_STRIP_ROWS);
+    println!("                         compressed independently in parallel)");
+    println!("  --no-strips            Store each frame as a single stream");
+    println!("  --threads <n>          Worker threads (default: all cores, or");
+    println!("                         RAYON_NUM_THREADS); output is identical");
+    println!();
+    println!("COLOR OPTIONS:");
+    println!("  --profile <profile>  Convert pixels to <profile> and embed it");
+    println!("  --intent <intent>    perceptual (default), relative, saturation, absolute");
+    println!("  --bpc                Enable black point compensation");
+    println!("  --hdr, --16bit       Store 16 bits per channel");
+    println!("  --color-model <m>    rgb (default), cmyk (needs a CMYK --profile), lab");
+    println!("  --max-error <n>      Near-lossless: color samples may differ by up to <n>");
+    println!("                       (alpha stays exact; 0 = lossless)");
+    println!();
+    println!("EXIF OPTIONS (JPEG, TIFF and PNG eXIf sources):");
+    println!("  --keep-exif          Also store the raw EXIF block so no tags are lost");
+    println!("  --no-exif            Do not import camera, DPI and orientation from EXIF");
+    println!("  --apply-orientation  Rotate/mirror the pixels upright and store \"normal\"");
+    println!();
+    println!("REPRODUCIBLE OUTPUT:");
+    println!("  SOURCE_DATE_EPOCH=<s> Use this time (seconds since 1970) for `created`");
+    println!("  --reproducible       Omit `created` unless SOURCE_DATE_EPOCH is set, so");
+    println!("                       identical input gives byte-identical files");
+    println!();
+    println!("BATCH OPTIONS (plus all compile options):");
+    println!("  --output <dir>       Write into <dir>, mirroring the input tree");
+    println!("  --include <glob>     Files to convert (repeatable, default *.png)");
+    println!("  --exclude <glob>     Files or directories to skip (repeatable)");
+    println!("  --report <file>      Per-file report (.csv for CSV, otherwise JSON)");
+    println!("  --force              Reconvert files whose .yeet is already up to date");
+    println!();
+    println!("VIEWER OPTIONS:");
+    println!("  --proof <profile>    Soft-proof against an output profile");
+    println!("  --gamut-warning      Paint pixels outside the proof gamut magenta");
     println!();
     println!("FEATURES:");
     println!("  ✅ ICC color profiles       Accurate color reproduction");
     println!("  ✅ Multi-frame animation    GIF/APNG alternative");
     println!("  ✅ Brotli/Zstd compression  Better than zlib");
     println!("  ✅ Rich metadata            Extended EXIF-like data");
-    println!("  🚧 HDR support              16-bit per channel (planned)");
+    println!("  ✅ HDR support              16-bit per channel (--hdr)");
     println!();
     println!("EXAMPLES:");
     println!("  # Convert with Brotli (best compression)");
@@ -824,6 +4223,9 @@ fn print_usage(program: &str) {
     println!("  # Convert with Zstd (fast)");
     println!("  {} compile photo.png --zstd --binary", program);
     println!();
+    println!("  # Convert a folder tree, skipping files that are up to date");
+    println!("  {} batch ./photos --brotli --binary --output ./yeet", program);
+    println!();
     println!("  # View YEET v3 file with animation");
     println!("  {} animation.yeet", program);
     println!();
@@ -832,7 +4234,949 @@ fn print_usage(program: &str) {
     println!("  - Play/Pause button");
     println!("  - Frame navigation (Prev/Next)");
     println!("  - ICC color correction applied");
+    println!("  - Rendering intent / BPC selection");
+    println!("  - Soft proofing with gamut warning");
     println!("  - Metadata viewer");
     println!();
-    println!("For stable/production use, see yeet-core (v2)");
+    println!("For stable/production use, see yeet-core (v2)");
+}
+
+// ============================================================================
+// Batch Commands
+// ============================================================================
+
+/// Options for the 'batch' command
+struct BatchOptions {
+    compile: CompileOptions,
+    filter: BatchFilter,
+    force: bool,
+}
+
+impl BatchOptions {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut include: Vec<String> = arg_values(args, "--include").map(str::to_string).collect();
+        if include.is_empty() {
+            include.push("*.png".to_string());
+        }
+        
+        Ok(Self {
+            compile: CompileOptions::from_args(args)?,
+            filter: BatchFilter {
+                include,
+                exclude: arg_values(args, "--exclude").map(str::to_string).collect(),
+                output_dir: arg_value(args, "--output").map(PathBuf::from),
+            },
+            force: args.contains(&"--force".to_string()),
+        })
+    }
+}
+
+/// Outcome of converting (or skipping) one file in a batch
+#[derive(Serialize)]
+struct BatchEntry {
+    input: PathBuf,
+    output: PathBuf,
+    input_size: u64,
+    output_size: u64,
+    ratio: f64,
+    time_seconds: f64,
+    skipped: bool,
+    encoding: Option<String>,
+    error: Option<String>,
+}
+
+impl ReportEntry for BatchEntry {
+    const CSV_HEADER: &'static str =
+        "input,output,input_size,output_size,ratio,time_seconds,skipped,encoding,error";
+    
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.input.display().to_string(),
+            self.output.display().to_string(),
+            self.input_size.to_string(),
+            self.output_size.to_string(),
+            format!("{:.4}", self.ratio),
+            format!("{:.4}", self.time_seconds),
+            self.skipped.to_string(),
+            self.encoding.clone().unwrap_or_default(),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Whether `output` exists and is at least as new as `input`
+fn is_up_to_date(input: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    matches!((modified(input), modified(output)), (Some(source), Some(target)) if target >= source)
+}
+
+/// Convert one batch file, mirroring its relative path under the output directory
+fn convert_batch_file(root: &Path, input: &Path, options: &BatchOptions) -> BatchEntry {
+    let relative = input.strip_prefix(root).unwrap_or(input);
+    let output = match &options.filter.output_dir {
+        Some(dir) => dir.join(relative).with_extension("yeet"),
+        None => input.with_extension("yeet"),
+    };
+    let input_size = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+    
+    let skipped = !options.force && is_up_to_date(input, &output);
+    let start = Instant::now();
+    let result = if skipped {
+        Ok(None)
+    } else {
+        png_to_yeet_image(input, &options.compile).and_then(|yeet_img| {
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if options.compile.auto {
+                let compile = &options.compile;
+                let (chosen, encoded, _) = optimize_encoding(&yeet_img, compile.level)?;
+                fs::write(&output, encoded)?;
+                Ok(describe_encoding(&chosen))
+            } else {
+                write_yeet_v3(&output, &yeet_img)?;
+                Ok(describe_encoding(&yeet_img))
+            }
+        })
+        .map(Some)
+    };
+    let time_seconds = start.elapsed().as_secs_f64();
+    
+    let (output_size, encoding, error) = match result {
+        Ok(encoding) => (fs::metadata(&output).map(|m| m.len()).unwrap_or(0), encoding, None),
+        Err(e) => (0, None, Some(e.to_string())),
+    };
+    
+    BatchEntry {
+        input: input.to_path_buf(),
+        output,
+        input_size,
+        output_size,
+        ratio: if input_size == 0 { 0.0 } else { output_size as f64 / input_size as f64 },
+        time_seconds,
+        skipped,
+        encoding,
+        error,
+    }
+}
+
+/// Handle 'optimize' command (re-encode an existing file as small as possible)
+fn handle_optimize(args: &[String]) -> Result<(), eframe::Error> {
+    let (path, yeet_img) = load_profile_command_input(
+        args,
+        "optimize <file.yeet> [--level <n>] [--output out.yeet]",
+    );
+    let original_size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let level = match auto_level_arg(args) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let (chosen, encoded, candidates) = match optimize_encoding(&yeet_img, level) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to encode: {}", e);
+            std::process::exit(1);
+        }
+    };
+    print_encoding_candidates(&candidates, &chosen);
+    
+    let output = arg_value(args, "--output").map(PathBuf::from).unwrap_or_else(|| path.clone());
+    if let Err(e) = fs::write(&output, &encoded) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Wrote {}", output.display());
+    // The level of the original file is not stored, so only its algorithm is known
+    println!(
+        "  Encoding: {} (was {} + {})",
+        describe_encoding(&chosen),
+        if yeet_img.is_binary { "binary" } else { "hex" },
+        yeet_img.compression.name()
+    );
+    println!(
+        "  Layout: {} (was {})",
+        describe_layout(chosen.width, chosen.metadata.tiles, chosen.metadata.interlace),
+        describe_layout(yeet_img.width, yeet_img.metadata.tiles, yeet_img.metadata.interlace)
+    );
+    println!("  Size: {} -> {} bytes", original_size, encoded.len());
+    Ok(())
+}
+
+/// Handle 'batch' command
+fn handle_batch(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No directory specified");
+        eprintln!("Usage: {} batch <directory> [options] [--output <dir>]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let dir_path = PathBuf::from(&args[2]);
+    if !dir_path.is_dir() {
+        eprintln!("[ERROR] Not a valid directory: {:?}", dir_path);
+        std::process::exit(1);
+    }
+    
+    let options = match BatchOptions::from_args(args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let mut files = Vec::new();
+    if let Err(e) = collect_batch_files(&dir_path, &dir_path, &options.filter, &mut files) {
+        eprintln!("[ERROR] Failed to read directory: {}", e);
+        std::process::exit(1);
+    }
+    
+    println!("[INFO] Starting batch conversion of {} files...", files.len());
+    
+    let progress = BatchProgress::new(files.len());
+    let entries: Vec<BatchEntry> = files
+        .par_iter()
+        .map(|path| {
+            let entry = convert_batch_file(&dir_path, path, &options);
+            progress.advance(&path.strip_prefix(&dir_path).unwrap_or(path).display().to_string());
+            entry
+        })
+        .collect();
+    
+    for entry in &entries {
+        if let Some(ref error) = entry.error {
+            eprintln!("  [ERROR] {}: {}", entry.input.display(), error);
+        }
+    }
+    
+    let errors = entries.iter().filter(|e| e.error.is_some()).count();
+    let skipped = entries.iter().filter(|e| e.skipped).count();
+    let converted: Vec<&BatchEntry> = entries.iter().filter(|e| !e.skipped && e.error.is_none()).collect();
+    
+    println!();
+    println!("[OK] Batch conversion complete");
+    println!("  Converted: {}", converted.len());
+    println!("  Up to date: {}", skipped);
+    println!("  Failed: {}", errors);
+    println!(
+        "  Total size: {} -> {} bytes",
+        converted.iter().map(|e| e.input_size).sum::<u64>(),
+        converted.iter().map(|e| e.output_size).sum::<u64>()
+    );
+    
+    if let Some(report) = arg_value(args, "--report") {
+        match write_batch_report(Path::new(report), &entries) {
+            Ok(()) => println!("  Report: {}", report),
+            Err(e) => {
+                eprintln!("[ERROR] Failed to write report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+// ============================================================================
+// Dictionary Commands
+// ============================================================================
+
+/// Handle 'train-dict' command (zstd dictionary from a folder of PNGs)
+///
+/// Samples are the frame data exactly as `compile` would compress it, so pass
+/// the same `--binary`/`--hdr`/`--color-model` options used for the real files.
+fn handle_train_dict(args: &[String]) -> Result<(), eframe::Error> {
+    if args.len() < 3 {
+        eprintln!("[ERROR] No directory specified");
+        eprintln!("Usage: {} train-dict <directory> --output set.dict [--dict-size bytes] [options]", args[0]);
+        std::process::exit(1);
+    }
+    
+    let dir_path = PathBuf::from(&args[2]);
+    if !dir_path.is_dir() {
+        eprintln!("[ERROR] Not a valid directory: {:?}", dir_path);
+        std::process::exit(1);
+    }
+    let Some(output) = arg_value(args, "--output").map(PathBuf::from) else {
+        eprintln!("[ERROR] No dictionary file given (--output set.dict)");
+        std::process::exit(1);
+    };
+    let dict_size = match arg_value(args, "--dict-size").map(str::parse::<usize>) {
+        None => DEFAULT_DICTIONARY_SIZE,
+        Some(Ok(size)) if size > 0 => size,
+        Some(_) => {
+            eprintln!("[ERROR] Invalid dictionary size");
+            std::process::exit(1);
+        }
+    };
+    let options = match BatchOptions::from_args(args) {
+        Ok(o) => BatchOptions { filter: BatchFilter { output_dir: None, ..o.filter }, ..o },
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let mut files = Vec::new();
+    if let Err(e) = collect_batch_files(&dir_path, &dir_path, &options.filter, &mut files) {
+        eprintln!("[ERROR] Failed to read directory: {}", e);
+        std::process::exit(1);
+    }
+    
+    println!("[INFO] Collecting samples from {} files...", files.len());
+    let samples: Vec<Vec<u8>> = files
+        .par_iter()
+        .filter_map(|path| match png_to_yeet_image(path, &options.compile) {
+            Ok(yeet_img) => Some(yeet_img.frames.into_iter().map(|frame| frame.data)),
+            Err(e) => {
+                eprintln!("  [WARN] Skipping {}: {}", path.display(), e);
+                None
+            }
+        })
+        .flatten_iter()
+        .collect();
+    
+    let dictionary = match zstd::dict::from_samples(&samples, dict_size) {
+        Ok(dictionary) => dictionary,
+        Err(e) => {
+            eprintln!("[ERROR] Training failed: {} (more or larger samples may help)", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = fs::write(&output, &dictionary) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    let id = zstd::zstd_safe::get_dict_id_from_dict(&dictionary).map_or(0, |id| id.get());
+    println!("[OK] Trained zstd dictionary: {}", output.display());
+    println!("  ID: {}", id);
+    println!("  Size: {} bytes from {} samples ({} bytes)",
+             dictionary.len(), samples.len(), samples.iter().map(Vec::len).sum::<usize>());
+    Ok(())
+}
+
+// ============================================================================
+// Metadata Commands
+// ============================================================================
+
+/// Metadata keys that describe how the payload is stored; `meta` never changes them
+const FORMAT_METADATA_KEYS: &[&str] = &[
+    "color_model", "frame_count", "loop_count", "bit_depth", "hdr", "codec",
+    "zstd_dictionary", "tiles", "pyramid_levels", "interlace", "quality",
+];
+
+/// Keys every v3 reader needs (`meta rm` refuses them, `meta strip` keeps them)
+const REQUIRED_METADATA_KEYS: &[&str] = &["software"];
+
+/// Keyed maps whose entries `meta` addresses as `map.entry`
+const MAP_METADATA_KEYS: &[&str] = &["text", "custom"];
+
+/// Offset of the metadata length (magic, version, flags, then four u32 fields)
+const METADATA_OFFSET: usize = 22;
+
+/// A v3 file split around its metadata; everything else stays raw bytes
+struct MetadataSections {
+    header: Vec<u8>,
+    metadata: serde_json::Map<String, serde_json::Value>,
+    payload: Vec<u8>,
+}
+
+impl MetadataSections {
+    fn read(path: &Path) -> Result<Self, std::io::Error> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < METADATA_OFFSET || &bytes[0..4] != b"YEET" {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid YEET file: wrong magic bytes"
+            ));
+        }
+        if bytes[4] != 3 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Expected v3, got v{} (use yeet-core meta for v2 files)", bytes[4])
+            ));
+        }
+        
+        let mut reader = &bytes[METADATA_OFFSET..];
+        let metadata_len = read_u32(&mut reader)? as usize;
+        let metadata_bytes = read_bytes(&mut reader, metadata_len)?;
+        let metadata = serde_json::from_slice(&metadata_bytes).map_err(|e| std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Malformed metadata: {}", e)
+        ))?;
+        
+        Ok(Self {
+            header: bytes[..METADATA_OFFSET].to_vec(),
+            metadata,
+            payload: reader.to_vec(),
+        })
+    }
+    
+    /// Write the header and payload unchanged around the new metadata
+    fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let metadata_json = serde_json::to_string(&self.metadata).map_err(std::io::Error::other)?;
+        let mut bytes = Vec::with_capacity(self.header.len() + 4 + metadata_json.len() + self.payload.len());
+        bytes.extend_from_slice(&self.header);
+        bytes.extend_from_slice(&(metadata_json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(metadata_json.as_bytes());
+        bytes.extend_from_slice(&self.payload);
+        fs::write(path, bytes)
+    }
+}
+
+/// Reject keys `meta` may not touch
+fn check_editable_key(key: &str) -> Result<(), String> {
+    let known = serde_json::to_value(YeetMetadataV3::default()).unwrap();
+    if known.get(key).is_none() && !MAP_METADATA_KEYS.contains(&key) {
+        let keys: Vec<&String> = known.as_object().unwrap().keys()
+            .filter(|k| !FORMAT_METADATA_KEYS.contains(&k.as_str()))
+            .collect();
+        return Err(format!(
+            "Unknown metadata key '{}' (editable: {}, text.<keyword>, custom.<key>)",
+            key,
+            keys.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    if FORMAT_METADATA_KEYS.contains(&key) {
+        return Err(format!("'{}' describes the pixel data and cannot be edited", key));
+    }
+    Ok(())
+}
+
+/// Split `text.Title` / `custom.app` into the map key and the entry
+fn split_map_key(key: &str) -> (&str, Option<&str>) {
+    match key.split_once('.') {
+        Some((map, entry)) if MAP_METADATA_KEYS.contains(&map) => (map, Some(entry)),
+        _ => (key, None),
+    }
+}
+
+/// Apply `key=value`; the value is JSON if that fits the key, otherwise a string
+fn set_metadata_field(
+    metadata: &mut serde_json::Map<String, serde_json::Value>,
+    assignment: &str,
+) -> Result<(), String> {
+    let Some((key, value)) = assignment.split_once('=') else {
+        return Err(format!("Expected key=value, got '{}'", assignment));
+    };
+    let (key, entry) = split_map_key(key);
+    if let (Some(keyword), "text") = (entry, key) {
+        if !valid_text_keyword(keyword) {
+            return Err(format!("Invalid text keyword '{}' (PNG keywords are 1-79 Latin-1 characters)", keyword));
+        }
+    }
+    check_editable_key(key)?;
+    
+    let previous = metadata.get(key).cloned();
+    let mut error = None;
+    let candidates = serde_json::from_str(value).ok().into_iter()
+        .chain(std::iter::once(serde_json::Value::String(value.to_string())));
+    for candidate in candidates {
+        let candidate = match entry {
+            Some(entry) => {
+                let mut map = previous.as_ref()
+                    .and_then(serde_json::Value::as_object)
+                    .cloned()
+                    .unwrap_or_default();
+                map.insert(entry.to_string(), candidate);
+                serde_json::Value::Object(map)
+            }
+            None => candidate,
+        };
+        // Only the edited key: other invalid values are left as they are
+        let checked = check_metadata_value(key, &candidate);
+        metadata.insert(key.to_string(), candidate);
+        match checked {
+            Ok(()) => return Ok(()),
+            Err(e) => error = error.or(Some(e)),
+        }
+    }
+    
+    match previous {
+        Some(previous) => metadata.insert(key.to_string(), previous),
+        None => metadata.remove(key),
+    };
+    Err(format!("Invalid value for '{}': {}", key, error.unwrap_or_default()))
+}
+
+/// Remove `key`, a `map.entry` or a key only newer tools know
+fn remove_metadata_field(
+    metadata: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Result<(), String> {
+    let known = serde_json::to_value(YeetMetadataV3::default()).unwrap();
+    let (map, entry) = split_map_key(key);
+    if known.get(map).is_none() && !MAP_METADATA_KEYS.contains(&map) && metadata.remove(key).is_some() {
+        return Ok(());
+    }
+    check_editable_key(map)?;
+    if REQUIRED_METADATA_KEYS.contains(&map) {
+        return Err(format!("'{}' is required and cannot be removed", map));
+    }
+    
+    let Some(entry) = entry else {
+        metadata.remove(key);
+        return Ok(());
+    };
+    // Drop the map once its last entry is gone
+    if let Some(serde_json::Value::Object(entries)) = metadata.get_mut(map) {
+        entries.remove(entry);
+        if entries.is_empty() {
+            metadata.remove(map);
+        }
+    }
+    Ok(())
+}
+
+/// Print one field per line (strings unquoted, everything else as JSON)
+fn print_metadata_fields(metadata: &serde_json::Map<String, serde_json::Value>) {
+    for (key, value) in metadata {
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::String(text) => println!("  {}: {}", key, text),
+            serde_json::Value::Object(entries) if MAP_METADATA_KEYS.contains(&key.as_str()) => {
+                for (entry, value) in entries {
+                    match value {
+                        serde_json::Value::String(text) => println!("  {}.{}: {}", key, entry, text),
+                        value => println!("  {}.{}: {}", key, entry, value),
+                    }
+                }
+            }
+            value => println!("  {}: {}", key, value),
+        }
+    }
+}
+
+/// Handle 'meta' command: show or edit metadata without re-encoding the pixels
+fn handle_meta(args: &[String]) -> Result<(), eframe::Error> {
+    let usage = "meta <show|set|rm|strip> <file.yeet> [key=value ...|key ...] [--output out.yeet]";
+    let (Some(action), Some(input)) = (args.get(2), args.get(3)) else {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} {}", args[0], usage);
+        std::process::exit(1);
+    };
+    let input = PathBuf::from(input);
+    
+    let mut sections = match MetadataSections::read(&input) {
+        Ok(sections) => sections,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load {}: {}", input.display(), e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Everything after the file name except `--output <path>`
+    let mut operands = Vec::new();
+    let mut rest = args[4..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--output" {
+            rest.next();
+        } else {
+            operands.push(arg.as_str());
+        }
+    }
+    
+    let result = match action.as_str() {
+        "show" => {
+            println!("[INFO] Metadata of {}:", input.display());
+            print_metadata_fields(&sections.metadata);
+            return Ok(());
+        }
+        "set" if operands.is_empty() => Err("Nothing to set (expected key=value)".to_string()),
+        "set" => operands.iter().try_for_each(|assignment| {
+            set_metadata_field(&mut sections.metadata, assignment)
+        }),
+        "rm" if operands.is_empty() => Err("Nothing to remove (expected a key)".to_string()),
+        "rm" => operands.iter().try_for_each(|key| remove_metadata_field(&mut sections.metadata, key)),
+        "strip" => {
+            sections.metadata.retain(|key, _| {
+                FORMAT_METADATA_KEYS.contains(&key.as_str()) || REQUIRED_METADATA_KEYS.contains(&key.as_str())
+            });
+            Ok(())
+        }
+        _ => Err(format!("Unknown meta action '{}' (expected show, set, rm or strip)", action)),
+    };
+    if let Err(e) = result {
+        eprintln!("[ERROR] {}", e);
+        std::process::exit(1);
+    }
+    
+    let output = arg_value(args, "--output").map(PathBuf::from).unwrap_or(input);
+    match sections.write(&output) {
+        Ok(()) => {
+            println!("[OK] Wrote {}", output.display());
+            print_metadata_fields(&sections.metadata);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle 'extract-xmp' command: write the XMP packet to a sidecar file
+fn handle_extract_xmp(args: &[String]) -> Result<(), eframe::Error> {
+    let Some(input) = args.get(2).map(PathBuf::from) else {
+        eprintln!("[ERROR] No input file");
+        eprintln!("Usage: {} extract-xmp <file.yeet> [--output sidecar.xmp]", args[0]);
+        std::process::exit(1);
+    };
+    
+    let sections = match MetadataSections::read(&input) {
+        Ok(sections) => sections,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load {}: {}", input.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let Some(xmp) = sections.metadata.get("xmp").and_then(serde_json::Value::as_str) else {
+        eprintln!("[ERROR] {} has no XMP packet", input.display());
+        std::process::exit(1);
+    };
+    
+    let output = arg_value(args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| input.with_extension("xmp"));
+    if let Err(e) = fs::write(&output, xmp) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Extracted XMP: {}", output.display());
+    println!("  Size: {} bytes", xmp.len());
+    Ok(())
+}
+
+/// Handle 'embed-xmp' command: store a sidecar's XMP packet without re-encoding the pixels
+fn handle_embed_xmp(args: &[String]) -> Result<(), eframe::Error> {
+    let (Some(input), Some(sidecar)) = (args.get(2).map(PathBuf::from), args.get(3)) else {
+        eprintln!("[ERROR] Missing input file or XMP sidecar");
+        eprintln!("Usage: {} embed-xmp <file.yeet> <sidecar.xmp> [--output out.yeet]", args[0]);
+        std::process::exit(1);
+    };
+    
+    let packet = match fs::read(sidecar).map(String::from_utf8) {
+        Ok(Ok(packet)) => packet,
+        Ok(Err(_)) => {
+            eprintln!("[ERROR] {} is not UTF-8 text", sidecar);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Failed to read {}: {}", sidecar, e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = check_xmp_packet(&packet) {
+        eprintln!("[ERROR] {}: {}", sidecar, e);
+        std::process::exit(1);
+    }
+    
+    let mut sections = match MetadataSections::read(&input) {
+        Ok(sections) => sections,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to load {}: {}", input.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let size = packet.len();
+    sections.metadata.insert("xmp".to_string(), serde_json::Value::String(packet));
+    
+    let output = arg_value(args, "--output").map(PathBuf::from).unwrap_or(input);
+    if let Err(e) = sections.write(&output) {
+        eprintln!("[ERROR] Failed to write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    
+    println!("[OK] Embedded XMP: {}", output.display());
+    println!("  Size: {} bytes", size);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Binary RGBA image with a distinct value in every sample
+    fn test_image(width: u32, height: u32, tiles: Option<TileLayout>) -> YeetImageV3 {
+        let data = (0..width * height * 4)
+            .map(|i| (i.wrapping_mul(31) ^ (i >> 7)) as u8)
+            .collect();
+        YeetImageV3 {
+            width,
+            height,
+            has_alpha: true,
+            is_hdr: false,
+            is_binary: true,
+            compression: CompressionAlgorithm::Zlib,
+            compression_level: None,
+            dictionary: None,
+            loop_count: 0,
+            metadata: YeetMetadataV3 { tiles, ..YeetMetadataV3::default() },
+            thumbnail: None,
+            icc_profile: None,
+            frames: vec![YeetFrame { delay: 0, data }],
+        }
+    }
+    
+    /// File in the temp directory, removed when dropped (also when a test panics)
+    struct TempFile(PathBuf);
+    
+    impl TempFile {
+        fn with_image(name: &str, yeet_img: &YeetImageV3) -> Self {
+            let file = TempFile(env::temp_dir().join(format!("yeet-v3-{}-{}.yeet", name, std::process::id())));
+            write_yeet_v3(&file.0, yeet_img).unwrap();
+            file
+        }
+    }
+    
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+    
+    fn encode(yeet_img: &YeetImageV3) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        write_yeet_v3_to(&mut encoded, yeet_img).unwrap();
+        encoded
+    }
+    
+    /// Write the image to a file and read it back
+    fn round_trip(name: &str, yeet_img: &YeetImageV3) -> YeetImageV3 {
+        read_yeet_v3(&TempFile::with_image(name, yeet_img).0, None).unwrap()
+    }
+    
+    /// Rows `y..y + height`, columns `x..x + width` of a raw RGBA raster
+    fn crop(raw: &[u8], stride: u32, (x, y, width, height): (u32, u32, u32, u32)) -> Vec<u8> {
+        (y..y + height)
+            .flat_map(|row| {
+                let start = ((row * stride + x) * 4) as usize;
+                raw[start..start + width as usize * 4].iter().copied()
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn decode_region_matches_a_crop_of_the_full_image() {
+        let yeet_img = test_image(37, 29, Some(TileLayout { width: 8, height: 6 }));
+        let file = TempFile::with_image("region", &yeet_img);
+        let full = read_yeet_v3(&file.0, None).unwrap();
+        let (_, mut reader) = read_yeet_v3_tiled(&file.0, None).unwrap();
+        
+        assert_eq!(full.frames[0].data, yeet_img.frames[0].data);
+        // Inside one tile, across tile edges, the whole image and clipped at the edges
+        for rect in [(1, 1, 3, 2), (5, 4, 13, 11), (0, 0, 37, 29), (30, 20, 7, 9)] {
+            let region = reader.decode_region(0, rect.0, rect.1, rect.2, rect.3).unwrap();
+            assert_eq!(region, crop(&full.frames[0].data, 37, rect), "region {:?}", rect);
+        }
+        let clipped = reader.decode_region(0, 30, 20, 100, 100).unwrap();
+        assert_eq!(clipped, crop(&full.frames[0].data, 37, (30, 20, 7, 9)));
+    }
+    
+    #[test]
+    fn adam7_round_trip() {
+        // Sizes smaller than, equal to and not a multiple of the 8×8 pass grid
+        for (width, height) in [(1, 1), (3, 5), (8, 8), (13, 7), (33, 17)] {
+            for bpp in [3, 4, 8] {
+                let raw: Vec<u8> = (0..width as usize * height as usize * bpp).map(|i| (i % 251) as u8).collect();
+                let interlaced = interlace_adam7(&raw, width, height, bpp);
+                assert_eq!(interlaced.len(), raw.len());
+                assert_eq!(deinterlace_adam7(&interlaced, width, height, bpp), raw, "{}x{} at {} bytes", width, height, bpp);
+            }
+        }
+    }
+    
+    #[test]
+    fn adam7_starts_with_the_first_pass() {
+        let raw: Vec<u8> = (0..16 * 16).map(|i| i as u8).collect();
+        let interlaced = interlace_adam7(&raw, 16, 16, 1);
+        // Pass 1 holds every eighth pixel of every eighth row
+        assert_eq!(&interlaced[..4], &[raw[0], raw[8], raw[128], raw[136]]);
+    }
+    
+    #[test]
+    fn adam7_file_round_trip() {
+        let mut yeet_img = test_image(19, 11, None);
+        yeet_img.metadata.interlace = Interlace::Adam7;
+        let decoded = round_trip("adam7", &yeet_img);
+        assert_eq!(decoded.metadata.interlace, Interlace::Adam7);
+        assert_eq!(decoded.frames[0].data, yeet_img.frames[0].data);
+    }
+    
+    #[test]
+    fn strip_and_tile_encoding_is_identical_for_any_thread_count() {
+        let strips = TileLayout { width: 64, height: 5 };
+        let tiles = TileLayout { width: 16, height: 16 };
+        for layout in [strips, tiles] {
+            let mut yeet_img = test_image(64, 48, Some(layout));
+            yeet_img.metadata.pyramid_levels = 2;
+            let encode_with = |threads: usize| {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+                pool.install(|| encode(&yeet_img))
+            };
+            let single = encode_with(1);
+            for threads in [2, 3, 8] {
+                assert!(encode_with(threads) == single, "{:?} differs with {} threads", layout, threads);
+            }
+        }
+    }
+    
+    #[test]
+    fn near_lossless_keeps_samples_within_max_error() {
+        let (width, height) = (23, 9);
+        for (channels, has_alpha, is_hdr) in [(3, false, false), (3, true, false), (1, true, false), (3, true, true)] {
+            let samples_per_pixel = channels + has_alpha as usize;
+            let bytes_per_sample = if is_hdr { 2 } else { 1 };
+            let original: Vec<u8> = (0..width * height * samples_per_pixel * bytes_per_sample)
+                .map(|i| ((i * 37) ^ (i / 5)) as u8)
+                .collect();
+            let sample = |raw: &[u8], index: usize| -> i64 {
+                if is_hdr {
+                    u16::from_be_bytes([raw[index * 2], raw[index * 2 + 1]]) as i64
+                } else {
+                    raw[index] as i64
+                }
+            };
+            
+            for max_error in [0, 1, 4, 30, max_sample_error(is_hdr)] {
+                let mut raw = original.clone();
+                quantize_near_lossless(&mut raw, width, channels, has_alpha, is_hdr, max_error);
+                for index in 0..width * height * samples_per_pixel {
+                    let difference = (sample(&raw, index) - sample(&original, index)).abs();
+                    if index % samples_per_pixel >= channels {
+                        assert_eq!(difference, 0, "alpha sample {} changed", index);
+                    } else {
+                        assert!(
+                            difference <= max_error as i64,
+                            "sample {} off by {} (max {}, {} channels, hdr {})",
+                            index, difference, max_error, channels, is_hdr
+                        );
+                    }
+                }
+                if max_error == 0 {
+                    assert_eq!(raw, original);
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn near_lossless_collapses_small_residuals() {
+        let mut raw = vec![100, 101, 99, 102, 98, 100];
+        quantize_near_lossless(&mut raw, 6, 1, false, false, 2);
+        assert_eq!(raw, vec![100; 6]);
+    }
+    
+    #[test]
+    fn source_pixel_for_every_orientation() {
+        // Stored 3×2 image; where the displayed top-left and top-right pixels come from
+        let (width, height) = (3, 2);
+        let expected = [
+            (Orientation::Normal, (0, 0), (2, 0)),
+            (Orientation::MirrorHorizontal, (2, 0), (0, 0)),
+            (Orientation::Rotate180, (2, 1), (0, 1)),
+            (Orientation::MirrorVertical, (0, 1), (2, 1)),
+            (Orientation::Transpose, (0, 0), (0, 1)),
+            (Orientation::Rotate90, (0, 1), (0, 0)),
+            (Orientation::Transverse, (2, 1), (2, 0)),
+            (Orientation::Rotate270, (2, 0), (2, 1)),
+        ];
+        assert_eq!(expected.len(), Orientation::ALL.len());
+        
+        for (orientation, top_left, top_right) in expected {
+            let (display_width, display_height) = orientation.display_size(width, height);
+            assert_eq!(orientation.source_pixel(0, 0, width, height), top_left, "{:?}", orientation);
+            assert_eq!(orientation.source_pixel(display_width - 1, 0, width, height), top_right, "{:?}", orientation);
+            
+            // Every stored pixel is shown exactly once, where `apply` puts it
+            let stored: Vec<u8> = (0..width * height).map(|i| i as u8).collect();
+            let displayed = orientation.apply(&stored, width, height, 1);
+            let mut seen = vec![false; stored.len()];
+            for y in 0..display_height {
+                for x in 0..display_width {
+                    let (sx, sy) = orientation.source_pixel(x, y, width, height);
+                    let index = (sy * width + sx) as usize;
+                    assert!(!seen[index], "{:?} shows ({}, {}) twice", orientation, sx, sy);
+                    seen[index] = true;
+                    assert_eq!(displayed[(y * display_width + x) as usize], stored[index], "{:?}", orientation);
+                }
+            }
+        }
+    }
+    
+    /// Two-frame lz4 image with a thumbnail and a pyramid level, kept in
+    /// `examples/` so yeet-core can test `inspect_v3` against it
+    fn inspection_example() -> Vec<u8> {
+        let mut yeet_img = test_image(12, 10, Some(TileLayout { width: 12, height: 4 }));
+        yeet_img.compression = CompressionAlgorithm::Lz4;
+        yeet_img.loop_count = 3;
+        yeet_img.metadata.created = Some("2024-01-01T00:00:00+00:00".to_string());
+        yeet_img.metadata.pyramid_levels = 1;
+        yeet_img.metadata.frame_count = 2;
+        yeet_img.metadata.loop_count = 3;
+        let second = yeet_img.frames[0].data.iter().map(|b| b.wrapping_add(1)).collect();
+        yeet_img.frames[0].delay = 100;
+        yeet_img.frames.push(YeetFrame { delay: 250, data: second });
+        yeet_img.thumbnail = Some(YeetThumbnail { width: 2, height: 2, data: vec![7; 16] });
+        encode(&yeet_img)
+    }
+    
+    /// Set `YEET_UPDATE_EXAMPLES=1` to rewrite the file after a format change
+    #[test]
+    fn inspection_example_is_up_to_date() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/animated-v3.yeet");
+        let encoded = inspection_example();
+        if env::var_os("YEET_UPDATE_EXAMPLES").is_some() {
+            fs::write(&path, &encoded).unwrap();
+        }
+        assert!(
+            fs::read(&path).unwrap() == encoded,
+            "{} is stale, rerun with YEET_UPDATE_EXAMPLES=1",
+            path.display()
+        );
+    }
+    
+    #[test]
+    fn truncated_streams_are_errors() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7 % 251) as u8).collect();
+        for algorithm in [CompressionAlgorithm::Lz4, CompressionAlgorithm::Lzma] {
+            let compressed = compress_data(&data, algorithm, None, None);
+            assert_eq!(decompress_data(&compressed, algorithm, None).unwrap(), data);
+            let truncated = &compressed[..compressed.len() / 2];
+            assert!(decompress_data(truncated, algorithm, None).is_err(), "truncated {:?}", algorithm);
+            assert!(decompress_data(b"definitely not compressed", algorithm, None).is_err(), "garbage {:?}", algorithm);
+        }
+    }
+    
+    #[test]
+    fn zstd_frames_need_the_dictionary_they_were_compressed_with() {
+        let samples: Vec<Vec<u8>> = (0..64u32)
+            .map(|n| (0..512u32).map(|i| ((i * (n % 5 + 1)) % 64 + n % 7) as u8).collect())
+            .collect();
+        let trained = zstd::dict::from_samples(&samples, 2048).unwrap();
+        let dictionary = ZstdDictionary::from_bytes(trained).unwrap();
+        
+        let compressed = compress_data(&samples[3], CompressionAlgorithm::Zstd, None, Some(&dictionary.data));
+        let decoded = decompress_data(&compressed, CompressionAlgorithm::Zstd, Some(&dictionary)).unwrap();
+        assert_eq!(decoded, samples[3]);
+        // Nothing is remembered between reads
+        assert!(decompress_data(&compressed, CompressionAlgorithm::Zstd, None).is_err());
+        assert!(decompress_prefix(&compressed, CompressionAlgorithm::Zstd, None).is_err());
+    }
+    
+    #[test]
+    fn icc_description_matches_lcms_for_builtin_profiles() {
+        for name in BUILTIN_PROFILES {
+            let profile = builtin_profile(name).unwrap();
+            let expected = profile.info(InfoType::Description, Locale::none());
+            assert!(expected.is_some(), "{}", name);
+            assert_eq!(icc_description(&profile.icc().unwrap()), expected, "{}", name);
+        }
+    }
 }